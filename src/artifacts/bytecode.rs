@@ -2,6 +2,7 @@
 
 use crate::{
     artifacts::{serde_helpers, FunctionDebugData, GeneratedSource, Offsets},
+    cbor::{self, CborMetadata},
     sourcemap::{self, SourceMap, SyntaxError},
     utils,
 };
@@ -295,6 +296,45 @@ impl BytecodeObject {
         self.as_bytes().map(|c| !c.0.is_empty()).unwrap_or_default()
     }
 
+    /// Returns the bytecode with the placeholders of unlinked libraries replaced by the zero
+    /// address.
+    ///
+    /// This keeps the positions of all instructions, so the result can be used for analysis that
+    /// doesn't depend on the library addresses, like mapping program counters to source locations.
+    /// Returns `None` if the object isn't valid hex apart from the placeholders.
+    pub fn bytes_with_zeroed_placeholders(&self) -> Option<Bytes> {
+        let unlinked = match self {
+            BytecodeObject::Bytecode(bytes) => return Some(bytes.clone()),
+            BytecodeObject::Unlinked(unlinked) => unlinked,
+        };
+        let unlinked = unlinked.strip_prefix("0x").unwrap_or(unlinked);
+        let mut code = String::with_capacity(unlinked.len());
+        let mut rest = unlinked;
+        // placeholders are 40 characters long and start with `__`, like `__$<hash>$__`
+        while let Some(start) = rest.find("__") {
+            let end = (start + 40).min(rest.len());
+            code.push_str(&rest[..start]);
+            code.extend(std::iter::repeat('0').take(end - start));
+            rest = &rest[end..];
+        }
+        code.push_str(rest);
+        hex::decode(code).ok().map(Into::into)
+    }
+
+    /// Decodes the CBOR metadata that the compiler appended to the bytecode.
+    ///
+    /// Returns `None` if the object is unlinked or doesn't end with valid metadata.
+    pub fn cbor_metadata(&self) -> Option<CborMetadata> {
+        CborMetadata::decode(self.as_bytes()?)
+    }
+
+    /// Returns the bytecode without the CBOR metadata at its end, if there is any.
+    ///
+    /// Returns `None` if the object is unlinked.
+    pub fn strip_cbor_metadata(&self) -> Option<Bytes> {
+        self.as_bytes().map(|code| Bytes::copy_from_slice(cbor::strip_metadata(code)))
+    }
+
     /// Tries to resolve the unlinked string object a valid bytecode object in place.
     ///
     /// Returns the string if it is a valid
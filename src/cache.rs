@@ -24,7 +24,93 @@ use std::{
 /// `ethers-solc` uses a different format version id, but the actual format is consistent with
 /// hardhat This allows ethers-solc to detect if the cache file was written by hardhat or
 /// `ethers-solc`
-const ETHERS_FORMAT_VERSION: &str = "ethers-rs-sol-cache-3";
+pub const ETHERS_FORMAT_VERSION: &str = "ethers-rs-sol-cache-4";
+
+/// All known versions of the cache file format.
+///
+/// Cache files written in an older format are migrated to [`CacheFormat::CURRENT`] when they are
+/// read, see [`CompilerCache::read()`].
+///
+/// `ethers-rs-sol-cache-2` and older caches aren't supported, since they don't track the project
+/// paths the cache was written for, and are rebuilt instead, like caches of other tools.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CacheFormat {
+    /// `ethers-rs-sol-cache-3`: settings are stored as `solcConfig.settings`.
+    V3,
+    /// Empty format id, written by releases that introduced the `Compiler` abstraction but did
+    /// not set the format version.
+    Unversioned,
+    /// `ethers-rs-sol-cache-4`: settings are stored as `compilerSettings`.
+    V4,
+}
+
+impl CacheFormat {
+    /// The format version written by this crate.
+    pub const CURRENT: Self = Self::V4;
+
+    /// Returns the format for the given `_format` id, if known.
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "ethers-rs-sol-cache-3" => Some(Self::V3),
+            "" => Some(Self::Unversioned),
+            ETHERS_FORMAT_VERSION => Some(Self::V4),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the `_format` id was written by a newer version of this crate, as opposed
+    /// to an older or a foreign format, like hardhat's.
+    pub fn is_newer_id(id: &str) -> bool {
+        let number = |id: &str| id.strip_prefix("ethers-rs-sol-cache-")?.parse::<u32>().ok();
+        number(id).zip(number(Self::CURRENT.id())).map_or(false, |(n, current)| n > current)
+    }
+
+    /// Returns the `_format` id of this format.
+    pub const fn id(&self) -> &'static str {
+        match self {
+            Self::V3 => "ethers-rs-sol-cache-3",
+            Self::Unversioned => "",
+            Self::V4 => ETHERS_FORMAT_VERSION,
+        }
+    }
+
+    /// Migrates the raw JSON `cache` from this format to [`CacheFormat::CURRENT`].
+    ///
+    /// Each step migrates to the next newer format until the current one is reached.
+    pub fn migrate(self, cache: &mut serde_json::Value) {
+        let mut format = self;
+        while format != Self::CURRENT {
+            trace!("migrating cache from format \"{}\"", format.id());
+            format = match format {
+                Self::V3 => {
+                    migrate_v3_to_v4(cache);
+                    Self::V4
+                }
+                Self::Unversioned | Self::V4 => Self::V4,
+            };
+        }
+        if let Some(obj) = cache.as_object_mut() {
+            obj.insert("_format".to_string(), Self::CURRENT.id().into());
+        }
+    }
+}
+
+/// Moves every entry's `solcConfig.settings` to `compilerSettings`.
+fn migrate_v3_to_v4(cache: &mut serde_json::Value) {
+    let Some(files) = cache.get_mut("files").and_then(|files| files.as_object_mut()) else {
+        return;
+    };
+    for entry in files.values_mut() {
+        let Some(entry) = entry.as_object_mut() else { continue };
+        if let Some(mut solc_config) = entry.remove("solcConfig") {
+            let settings = solc_config
+                .get_mut("settings")
+                .map(serde_json::Value::take)
+                .unwrap_or_else(|| serde_json::Value::Object(Default::default()));
+            entry.insert("compilerSettings".to_string(), settings);
+        }
+    }
+}
 
 /// The file name of the default cache file
 pub const SOLIDITY_FILES_CACHE_FILENAME: &str = "solidity-files-cache.json";
@@ -87,11 +173,14 @@ impl<S: CompilerSettings> CompilerCache<S> {
 
     /// Reads the cache json file from the given path
     ///
+    /// Caches written in an older [`CacheFormat`] are migrated to the current format.
+    ///
     /// See also [`Self::read_joined()`]
     ///
     /// # Errors
     ///
-    /// If the cache file does not exist
+    /// If the cache file does not exist, or if it was written in an unknown format, for example by
+    /// a newer version of this crate, see [`SolcError::UnknownCacheFormat`].
     ///
     /// # Examples
     ///
@@ -107,7 +196,15 @@ impl<S: CompilerSettings> CompilerCache<S> {
     pub fn read(path: impl AsRef<Path>) -> Result<Self> {
         let path = path.as_ref();
         trace!("reading solfiles cache at {}", path.display());
-        let cache: Self = utils::read_json_file(path)?;
+        let mut cache: serde_json::Value = utils::read_json_file(path)?;
+        if let Some(id) = cache.get("_format").and_then(|format| format.as_str()) {
+            let format = CacheFormat::from_id(id).ok_or_else(|| SolcError::UnknownCacheFormat {
+                format: id.to_string(),
+                path: path.to_path_buf(),
+            })?;
+            format.migrate(&mut cache);
+        }
+        let cache: Self = serde_json::from_value(cache)?;
         trace!("read cache \"{}\" with {} entries", cache.format, cache.files.len());
         Ok(cache)
     }
@@ -351,7 +448,7 @@ impl<S> Default for CompilerCache<S> {
 impl<'a, S: CompilerSettings> From<&'a ProjectPathsConfig> for CompilerCache<S> {
     fn from(config: &'a ProjectPathsConfig) -> Self {
         let paths = config.paths_relative();
-        CompilerCache::new(Default::default(), paths)
+        CompilerCache::new(ETHERS_FORMAT_VERSION.to_string(), paths)
     }
 }
 
@@ -805,25 +902,39 @@ impl<'a, T: ArtifactOutput, C: Compiler> ArtifactsCache<'a, T, C> {
     pub fn new(project: &'a Project<C, T>, edges: GraphEdges<C::ParsedSource>) -> Result<Self> {
         /// Returns the [SolFilesCache] to use
         ///
-        /// Returns a new empty cache if the cache does not exist or `invalidate_cache` is set.
+        /// Returns a new empty cache if the cache does not exist, can't be read, for example
+        /// because it was written by another tool like hardhat, or `invalidate_cache` is set.
+        ///
+        /// Fails if the cache was written by a newer version of this crate, instead of overwriting
+        /// it, see [`CacheFormat::is_newer_id()`].
         fn get_cache<T: ArtifactOutput, C: Compiler>(
             project: &Project<C, T>,
             invalidate_cache: bool,
-        ) -> CompilerCache<C::Settings> {
+        ) -> Result<CompilerCache<C::Settings>> {
             // the currently configured paths
             let paths = project.paths.paths_relative();
 
             if !invalidate_cache && project.cache_path().exists() {
-                if let Ok(cache) = CompilerCache::read_joined(&project.paths) {
-                    if cache.paths == paths {
-                        // unchanged project paths
-                        return cache;
+                match CompilerCache::read_joined(&project.paths) {
+                    Ok(cache) => {
+                        if cache.paths == paths {
+                            // unchanged project paths
+                            return Ok(cache);
+                        }
+                    }
+                    Err(SolcError::UnknownCacheFormat { format, path })
+                        if CacheFormat::is_newer_id(&format) =>
+                    {
+                        return Err(SolcError::UnknownCacheFormat { format, path });
+                    }
+                    Err(err) => {
+                        trace!("failed to read cache, creating a new one: {err}");
                     }
                 }
             }
 
             // new empty cache
-            CompilerCache::new(Default::default(), paths)
+            Ok(CompilerCache::new(ETHERS_FORMAT_VERSION.to_string(), paths))
         }
 
         let cache = if project.cached {
@@ -833,7 +944,7 @@ impl<'a, T: ArtifactOutput, C: Compiler> ArtifactsCache<'a, T, C> {
             let invalidate_cache = !edges.unresolved_imports().is_empty();
 
             // read the cache file if it already exists
-            let mut cache = get_cache(project, invalidate_cache);
+            let mut cache = get_cache(project, invalidate_cache)?;
 
             cache.remove_missing_files();
 
@@ -987,3 +1098,95 @@ impl<'a, T: ArtifactOutput, C: Compiler> ArtifactsCache<'a, T, C> {
         Ok(cached_artifacts)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Solc;
+
+    #[test]
+    fn can_migrate_v3_cache() {
+        // an `ethers-rs-sol-cache-2` file, which isn't supported anymore
+        let fixture =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test-data/solidity-files-cache.json");
+        let err = CompilerCache::<Settings>::read(&fixture).unwrap_err();
+        assert!(matches!(err, SolcError::UnknownCacheFormat { .. }), "{err}");
+
+        // its entries are stored the same way in v3, which also tracks the project paths
+        let mut cache: serde_json::Value = utils::read_json_file(&fixture).unwrap();
+        let paths = ProjectPaths { sources: "src".into(), ..Default::default() };
+        cache["_format"] = "ethers-rs-sol-cache-3".into();
+        cache["paths"] = serde_json::to_value(&paths).unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join(SOLIDITY_FILES_CACHE_FILENAME);
+        utils::write_json_file(&cache, &path, 1024).unwrap();
+
+        let cache = CompilerCache::<Settings>::read(&path).unwrap();
+        assert_eq!(cache.format, ETHERS_FORMAT_VERSION);
+        assert_eq!(cache.paths, paths);
+        assert!(!cache.is_empty());
+        for entry in cache.entries() {
+            assert_eq!(entry.compiler_settings.evm_version, Some(crate::EvmVersion::London));
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_cache_format() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join(SOLIDITY_FILES_CACHE_FILENAME);
+        let cache = CompilerCache::<Settings> {
+            format: "ethers-rs-sol-cache-99".to_string(),
+            ..Default::default()
+        };
+        cache.write(&path).unwrap();
+
+        let err = CompilerCache::<Settings>::read(&path).unwrap_err();
+        assert!(matches!(err, SolcError::UnknownCacheFormat { .. }), "{err}");
+
+        assert!(CacheFormat::is_newer_id("ethers-rs-sol-cache-99"));
+        assert!(!CacheFormat::is_newer_id(ETHERS_FORMAT_VERSION));
+        assert!(!CacheFormat::is_newer_id("ethers-rs-sol-cache-2"));
+        assert!(!CacheFormat::is_newer_id("hh-sol-cache-2"));
+    }
+
+    #[test]
+    fn rebuilds_foreign_cache_formats_only() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = utils::canonicalized(tmp.path());
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/A.sol"), "contract A {}").unwrap();
+        let paths = ProjectPathsConfig::builder().root(&root).build::<Solc>().unwrap();
+        let solc = Solc::new_with_version("solc", Version::new(0, 8, 19));
+        let project =
+            Project::builder().paths(paths).build(crate::CompilerConfig::Specific(solc)).unwrap();
+        let cache_with_format = |format: &str| {
+            let cache = CompilerCache::<Settings> {
+                format: format.to_string(),
+                paths: project.paths.paths_relative(),
+                ..Default::default()
+            };
+            cache.write(project.cache_path()).unwrap();
+            let (_, edges) = Graph::resolve(&project.paths).unwrap().into_sources();
+            ArtifactsCache::new(&project, edges)
+        };
+
+        let Ok(ArtifactsCache::Cached(cache)) = cache_with_format("hh-sol-cache-2") else {
+            panic!("expected cached project")
+        };
+        assert_eq!(cache.cache.format, ETHERS_FORMAT_VERSION);
+
+        let Err(err) = cache_with_format("ethers-rs-sol-cache-99") else {
+            panic!("expected unknown cache format")
+        };
+        assert!(matches!(err, SolcError::UnknownCacheFormat { .. }), "{err}");
+    }
+
+    #[test]
+    fn can_read_current_cache_format() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join(SOLIDITY_FILES_CACHE_FILENAME);
+        let cache = CompilerCache::<Settings>::default();
+        cache.write(&path).unwrap();
+        assert_eq!(CompilerCache::<Settings>::read(&path).unwrap(), cache);
+    }
+}
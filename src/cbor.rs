@@ -0,0 +1,222 @@
+//! Decoding of the CBOR encoded metadata that solc appends to the bytecode.
+//!
+//! See also <https://docs.soliditylang.org/en/latest/metadata.html#encoding-of-the-metadata-hash-in-the-bytecode>
+
+use crate::artifacts::BytecodeHash;
+use alloy_primitives::{Bytes, B256};
+use semver::Version;
+use std::ops::Range;
+
+/// The hash of the metadata file that's part of the [CborMetadata].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MetadataHash {
+    /// The IPFS multihash of the metadata
+    Ipfs(Bytes),
+    /// The Swarm hash of the metadata, used before solc 0.5.12
+    Bzzr0(B256),
+    /// The Swarm hash of the metadata
+    Bzzr1(B256),
+    /// The metadata hash was disabled with [`BytecodeHash::None`]
+    None,
+}
+
+impl MetadataHash {
+    /// Returns the setting that produces this kind of hash.
+    ///
+    /// Returns `None` for [`MetadataHash::Bzzr0`], which can't be selected.
+    pub fn bytecode_hash(&self) -> Option<BytecodeHash> {
+        match self {
+            MetadataHash::Ipfs(_) => Some(BytecodeHash::Ipfs),
+            MetadataHash::Bzzr0(_) => None,
+            MetadataHash::Bzzr1(_) => Some(BytecodeHash::Bzzr1),
+            MetadataHash::None => Some(BytecodeHash::None),
+        }
+    }
+}
+
+/// The CBOR encoded metadata that solc appends to the bytecode, unless disabled via
+/// [`SettingsMetadata::cbor_metadata`](crate::artifacts::SettingsMetadata::cbor_metadata).
+///
+/// The metadata is followed by its length as a big-endian `u16`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CborMetadata {
+    /// The hash of the metadata file
+    pub hash: MetadataHash,
+    /// The version of the compiler, which is only included since solc 0.5.9
+    pub solc: Option<Version>,
+    /// Whether experimental features are used
+    pub experimental: bool,
+    /// The length of the trailer in bytes, including the two length bytes
+    pub len: usize,
+}
+
+impl CborMetadata {
+    /// Decodes the metadata at the end of the code.
+    ///
+    /// Returns `None` if the code doesn't end with valid CBOR metadata.
+    pub fn decode(code: &[u8]) -> Option<Self> {
+        let [.., hi, lo] = *code else { return None };
+        let len = u16::from_be_bytes([hi, lo]) as usize;
+        let start = code.len().checked_sub(len + 2)?;
+        let mut decoder = Decoder { data: &code[start..code.len() - 2] };
+
+        let (5, entries) = decoder.header()? else { return None };
+        let mut metadata =
+            Self { hash: MetadataHash::None, solc: None, experimental: false, len: len + 2 };
+        for _ in 0..entries {
+            let Value::Text(key) = decoder.value()? else { return None };
+            match (key, decoder.value()?) {
+                ("ipfs", Value::Bytes(hash)) => {
+                    metadata.hash = MetadataHash::Ipfs(Bytes::copy_from_slice(hash))
+                }
+                ("bzzr0", Value::Bytes(hash)) if hash.len() == 32 => {
+                    metadata.hash = MetadataHash::Bzzr0(B256::from_slice(hash))
+                }
+                ("bzzr1", Value::Bytes(hash)) if hash.len() == 32 => {
+                    metadata.hash = MetadataHash::Bzzr1(B256::from_slice(hash))
+                }
+                // releases are encoded as bytes, pre-releases as string
+                ("solc", Value::Bytes(&[major, minor, patch])) => {
+                    metadata.solc = Some(Version::new(major as u64, minor as u64, patch as u64))
+                }
+                ("solc", Value::Text(version)) => metadata.solc = Version::parse(version).ok(),
+                ("experimental", Value::Bool(experimental)) => metadata.experimental = experimental,
+                _ => {}
+            }
+        }
+
+        decoder.data.is_empty().then_some(metadata)
+    }
+}
+
+/// Returns the code without the CBOR metadata at its end, if there is any.
+///
+/// This is useful to compare bytecode across builds, since the metadata hash changes with every
+/// change of the sources or settings, even if the code is the same.
+pub fn strip_metadata(code: &[u8]) -> &[u8] {
+    match CborMetadata::decode(code) {
+        Some(metadata) => &code[..code.len() - metadata.len],
+        None => code,
+    }
+}
+
+/// Returns the ranges of all CBOR metadata in the code, including the metadata of contracts whose
+/// creation code is embedded in the code, like the contracts deployed by a factory.
+///
+/// Only metadata that contains a hash or the compiler version is considered, to avoid matching
+/// arbitrary code that happens to be valid CBOR.
+pub fn metadata_ranges(code: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut end = code.len();
+    while end >= 2 {
+        match CborMetadata::decode(&code[..end]) {
+            Some(metadata) if metadata.hash != MetadataHash::None || metadata.solc.is_some() => {
+                ranges.push(end - metadata.len..end);
+                end -= metadata.len;
+            }
+            _ => end -= 1,
+        }
+    }
+    ranges.reverse();
+    ranges
+}
+
+/// The values of a CBOR map that can be part of the metadata.
+enum Value<'a> {
+    Uint,
+    Bytes(&'a [u8]),
+    Text(&'a str),
+    Bool(bool),
+}
+
+/// A decoder for the subset of CBOR that's used for the metadata.
+struct Decoder<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            return None;
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Some(taken)
+    }
+
+    /// Returns the major type and the argument of the next item.
+    fn header(&mut self) -> Option<(u8, u64)> {
+        let byte = self.take(1)?[0];
+        let argument = match byte & 0x1f {
+            info @ 0..=23 => info as u64,
+            24 => self.take(1)?[0] as u64,
+            25 => u16::from_be_bytes(self.take(2)?.try_into().ok()?) as u64,
+            26 => u32::from_be_bytes(self.take(4)?.try_into().ok()?) as u64,
+            27 => u64::from_be_bytes(self.take(8)?.try_into().ok()?),
+            _ => return None,
+        };
+        Some((byte >> 5, argument))
+    }
+
+    fn value(&mut self) -> Option<Value<'a>> {
+        let value = match self.header()? {
+            (0, _) => Value::Uint,
+            (2, len) => Value::Bytes(self.take(len.try_into().ok()?)?),
+            (3, len) => Value::Text(std::str::from_utf8(self.take(len.try_into().ok()?)?).ok()?),
+            (7, 20) => Value::Bool(false),
+            (7, 21) => Value::Bool(true),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::hex;
+
+    #[test]
+    fn can_decode_metadata() {
+        // {"ipfs": <34 bytes>, "solc": 0.8.19}
+        let ipfs = "1220".to_string() + &"ab".repeat(32);
+        let code =
+            hex::decode(format!("6080604052a264697066735822{ipfs}64736f6c634300081300330033"))
+                .unwrap();
+        // the trailer is followed by a fake length, so it doesn't decode
+        assert_eq!(CborMetadata::decode(&code), None);
+        assert_eq!(strip_metadata(&code), &code);
+
+        let code = &code[..code.len() - 2];
+        let metadata = CborMetadata::decode(code).unwrap();
+        assert_eq!(metadata.hash, MetadataHash::Ipfs(hex::decode(&ipfs).unwrap().into()));
+        assert_eq!(metadata.hash.bytecode_hash(), Some(BytecodeHash::Ipfs));
+        assert_eq!(metadata.solc, Some(Version::new(0, 8, 19)));
+        assert!(!metadata.experimental);
+        assert_eq!(metadata.len, 53);
+        assert_eq!(strip_metadata(code), hex::decode("6080604052").unwrap());
+
+        // {"bzzr0": <32 bytes>, "experimental": true}
+        let code = hex::decode(format!(
+            "00a265627a7a72305820{}6c6578706572696d656e74616cf50037",
+            "cd".repeat(32)
+        ))
+        .unwrap();
+        let metadata = CborMetadata::decode(&code).unwrap();
+        assert_eq!(metadata.hash, MetadataHash::Bzzr0(B256::repeat_byte(0xcd)));
+        assert_eq!(metadata.solc, None);
+        assert!(metadata.experimental);
+        assert_eq!(strip_metadata(&code), &[0]);
+
+        // {"solc": "0.8.20-nightly"}
+        let code = hex::decode("a164736f6c636e302e382e32302d6e696768746c790015").unwrap();
+        let metadata = CborMetadata::decode(&code).unwrap();
+        assert_eq!(metadata.hash, MetadataHash::None);
+        assert_eq!(metadata.solc, Some("0.8.20-nightly".parse().unwrap()));
+
+        // the creation code of a child contract with its own metadata, followed by the metadata of
+        // the parent
+        let code = hex::decode("6080a164736f6c6343000813000a00a164736f6c6343000814000a").unwrap();
+        assert_eq!(metadata_ranges(&code), [2..14, 15..27]);
+    }
+}
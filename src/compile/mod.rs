@@ -23,6 +23,8 @@ pub use output::{contracts, info, sources};
 
 pub mod project;
 
+pub mod watch;
+
 /// The name of the `solc` binary on the system
 pub const SOLC: &str = "solc";
 
@@ -142,12 +142,18 @@ impl<'a, T: ArtifactOutput, C: Compiler> ProjectCompiler<'a, T, C> {
     /// Multiple (`Solc` -> `Sources`) pairs can be compiled in parallel if the `Project` allows
     /// multiple `jobs`, see [`crate::Project::set_solc_jobs()`].
     pub fn with_sources(project: &'a Project<C, T>, sources: Sources) -> Result<Self> {
+        Self::with_graph(project, Graph::resolve_sources(&project.paths, sources)?)
+    }
+
+    /// Same as [`Self::with_sources()`] but with an already resolved dependency graph of the
+    /// sources.
+    pub fn with_graph(project: &'a Project<C, T>, graph: Graph<C::ParsedSource>) -> Result<Self> {
         match &project.compiler_config {
             CompilerConfig::Specific(compiler) => {
-                Self::with_sources_and_compiler(project, sources, compiler.clone())
+                Ok(Self::with_graph_and_compiler(project, graph, compiler.clone()))
             }
             CompilerConfig::AutoDetect(vm) => {
-                Self::with_sources_and_version_manager(project, sources, vm.clone())
+                Self::with_graph_and_version_manager(project, graph, vm.clone())
             }
         }
     }
@@ -159,7 +165,19 @@ impl<'a, T: ArtifactOutput, C: Compiler> ProjectCompiler<'a, T, C> {
         version_manager: VM,
     ) -> Result<Self> {
         let graph = Graph::resolve_sources(&project.paths, sources)?;
-        let (versions, edges) = graph.into_sources_by_version(project.offline, &version_manager)?;
+        Self::with_graph_and_version_manager(project, graph, version_manager)
+    }
+
+    fn with_graph_and_version_manager<VM: CompilerVersionManager<Compiler = C>>(
+        project: &'a Project<C, T>,
+        graph: Graph<C::ParsedSource>,
+        version_manager: VM,
+    ) -> Result<Self> {
+        let (versions, edges) = graph.into_sources_by_version_with_policy(
+            project.offline,
+            &version_manager,
+            &project.version_policy,
+        )?;
 
         let sources_by_version = versions.get(&version_manager)?;
 
@@ -180,13 +198,22 @@ impl<'a, T: ArtifactOutput, C: Compiler> ProjectCompiler<'a, T, C> {
         sources: Sources,
         compiler: C,
     ) -> Result<Self> {
+        let graph = Graph::resolve_sources(&project.paths, sources)?;
+        Ok(Self::with_graph_and_compiler(project, graph, compiler))
+    }
+
+    fn with_graph_and_compiler(
+        project: &'a Project<C, T>,
+        graph: Graph<C::ParsedSource>,
+        compiler: C,
+    ) -> Self {
         let version = compiler.version().clone();
-        let (sources, edges) = Graph::resolve_sources(&project.paths, sources)?.into_sources();
+        let (sources, edges) = graph.into_sources();
 
         let sources_by_version = vec![(compiler, version.clone(), sources)];
         let sources = CompilerSources::Sequential(sources_by_version);
 
-        Ok(Self { edges, project, sources, sparse_output: Default::default() })
+        Self { edges, project, sources, sparse_output: Default::default() }
     }
 
     /// Applies the specified filter to be applied when selecting solc output for
@@ -518,6 +545,10 @@ fn compile_sequential<C: Compiler>(
     let mut include_paths = paths.include_paths.clone();
     include_paths.extend(graph.include_paths().clone());
 
+    // Include remappings for all imports resolved by custom import resolvers.
+    let mut remappings = paths.remappings.clone();
+    remappings.extend(graph.import_remappings().iter().cloned());
+
     for (compiler, version, filtered_sources) in input {
         if filtered_sources.is_empty() {
             // nothing to compile
@@ -558,7 +589,7 @@ fn compile_sequential<C: Compiler>(
                 input.sources().keys()
             );
 
-            let mut input = input.with_remappings(paths.remappings.clone());
+            let mut input = input.with_remappings(remappings.clone());
             input.strip_prefix(paths.root.as_path());
 
             let start = Instant::now();
@@ -607,6 +638,10 @@ fn compile_parallel<C: Compiler>(
     let mut include_paths = paths.include_paths.clone();
     include_paths.extend(graph.include_paths().clone());
 
+    // Include remappings for all imports resolved by custom import resolvers.
+    let mut remappings = paths.remappings.clone();
+    remappings.extend(graph.import_remappings().iter().cloned());
+
     let mut jobs = Vec::with_capacity(versioned_sources.len());
     for (compiler, version, filtered_sources) in versioned_sources {
         if filtered_sources.is_empty() {
@@ -648,7 +683,7 @@ fn compile_parallel<C: Compiler>(
                 input.sources().keys()
             );
 
-            let mut input = input.with_remappings(paths.remappings.clone());
+            let mut input = input.with_remappings(remappings.clone());
             input.strip_prefix(paths.root.as_path());
 
             jobs.push((compiler.clone(), version.clone(), input, actually_dirty));
@@ -0,0 +1,508 @@
+//! Watch mode that recompiles a [`Project`] whenever its files change.
+//!
+//! The [`ProjectWatcher`] periodically checks the modification time and size of all source files
+//! in the sources, tests and scripts directories of the project, in the library directories the
+//! remappings point to, of all other files that are part of the project's [`Graph`], like library
+//! files imported by relative path, and of the `remappings.txt` file in the project root. Once a
+//! change is detected, it waits until no further changes occur for the configured debounce
+//! duration, so that saving multiple files or a `git checkout` results in a single recompilation.
+//!
+//! The changed files are then resolved against the project's [`Graph`], changes that don't affect
+//! any file of the graph are ignored. Otherwise the project is recompiled from that graph, and the
+//! cache decides which files are rebuilt.
+
+use crate::{
+    compile::project::ProjectCompiler,
+    compilers::{Compiler, ParsedSource},
+    error::Result,
+    remappings::Remapping,
+    resolver::Graph,
+    utils, ArtifactOutput, ConfigurableArtifacts, Project, ProjectCompileOutput, Solc,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// The name of the file in the project root that remappings are reloaded from
+pub const REMAPPINGS_FILE: &str = "remappings.txt";
+
+/// The files that were added, modified or removed between two checks of a [`ProjectWatcher`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileChanges {
+    /// Source files that were created
+    pub added: BTreeSet<PathBuf>,
+    /// Source files whose modification time or size changed
+    pub modified: BTreeSet<PathBuf>,
+    /// Source files that were deleted
+    pub removed: BTreeSet<PathBuf>,
+    /// Whether the `remappings.txt` file of the project was created, modified or deleted
+    pub remappings_changed: bool,
+}
+
+impl FileChanges {
+    /// Returns the changes between two snapshots, `remappings` is the path of the remappings file
+    fn new(old: &Snapshot, new: &Snapshot, remappings: &Path) -> Self {
+        let mut changes = Self::default();
+        for (file, stamp) in new {
+            match old.get(file) {
+                Some(old) if old == stamp => continue,
+                _ if file == remappings => changes.remappings_changed = true,
+                Some(_) => {
+                    changes.modified.insert(file.clone());
+                }
+                None => {
+                    changes.added.insert(file.clone());
+                }
+            }
+        }
+        for file in old.keys().filter(|file| !new.contains_key(*file)) {
+            if file == remappings {
+                changes.remappings_changed = true;
+            } else {
+                changes.removed.insert(file.clone());
+            }
+        }
+        changes
+    }
+
+    /// Returns `true` if nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.modified.is_empty()
+            && self.removed.is_empty()
+            && !self.remappings_changed
+    }
+
+    /// Returns an iterator over all added, modified and removed files
+    pub fn files(&self) -> impl Iterator<Item = &PathBuf> {
+        self.added.iter().chain(&self.modified).chain(&self.removed)
+    }
+}
+
+/// The result of a recompilation triggered by a [`ProjectWatcher`].
+#[derive(Debug)]
+pub struct WatchOutput<E, T: ArtifactOutput> {
+    /// The changes that triggered the recompilation, empty for the initial compilation
+    pub changes: FileChanges,
+    /// The output of the compilation
+    pub output: Result<ProjectCompileOutput<E, T>>,
+}
+
+/// Modification time and size of a file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    /// Returns the stamp of the file, or `None` if it doesn't exist
+    fn read(file: &Path) -> Option<Self> {
+        let metadata = fs::metadata(file).ok()?;
+        Some(Self { modified: metadata.modified().ok(), len: metadata.len() })
+    }
+}
+
+type Snapshot = BTreeMap<PathBuf, FileStamp>;
+
+/// The files of a graph mapped to the files that directly import them
+type Importers = BTreeMap<PathBuf, BTreeSet<PathBuf>>;
+
+/// Returns the files of the graph and their direct importers
+fn importers<D: ParsedSource>(graph: &Graph<D>) -> Importers {
+    let mut importers = Importers::new();
+    for (file, idx) in graph.files() {
+        importers.entry(file.clone()).or_default();
+        for dep in graph.imported_nodes(*idx) {
+            importers
+                .entry(graph.node(*dep).path().to_path_buf())
+                .or_default()
+                .insert(file.clone());
+        }
+    }
+    importers
+}
+
+/// Recompiles a [`Project`] whenever its files change.
+///
+/// # Examples
+///
+/// ```no_run
+/// use foundry_compilers::{watch::ProjectWatcher, CompilerConfig, Project, Solc};
+/// use std::{ops::ControlFlow, time::Duration};
+///
+/// let project = Project::builder().build(CompilerConfig::Specific(Solc::new("solc")?))?;
+/// ProjectWatcher::new(project).with_debounce(Duration::from_millis(200)).watch(|out| {
+///     match out.output {
+///         Ok(output) => println!("{output}"),
+///         Err(err) => eprintln!("{err}"),
+///     }
+///     ControlFlow::Continue(())
+/// });
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct ProjectWatcher<C: Compiler = Solc, T: ArtifactOutput = ConfigurableArtifacts> {
+    project: Project<C, T>,
+    /// How often the files are checked for changes
+    poll_interval: Duration,
+    /// How long no changes must occur before recompiling
+    debounce: Duration,
+    /// The state of the files at the last check
+    snapshot: Snapshot,
+    /// The files of the last resolved graph and the files that directly import them, used to find
+    /// the importers of removed files and the library files to watch
+    importers: Importers,
+    /// The remappings of the project that aren't declared in the remappings file
+    configured_remappings: Vec<Remapping>,
+}
+
+impl<C: Compiler, T: ArtifactOutput> ProjectWatcher<C, T> {
+    /// Creates a watcher for the project, with a poll interval of 100ms and a debounce of 50ms
+    pub fn new(project: Project<C, T>) -> Self {
+        let mut watcher = Self {
+            project,
+            poll_interval: Duration::from_millis(100),
+            debounce: Duration::from_millis(50),
+            snapshot: Snapshot::new(),
+            importers: Importers::new(),
+            configured_remappings: Vec::new(),
+        };
+        let declared = watcher.read_remappings_file();
+        watcher.configured_remappings = watcher
+            .project
+            .paths
+            .remappings
+            .iter()
+            .filter(|remapping| !declared.contains(remapping))
+            .cloned()
+            .collect();
+        if let Ok(graph) = Graph::resolve(&watcher.project.paths) {
+            watcher.importers = importers(&graph);
+        }
+        watcher.snapshot = watcher.snapshot();
+        watcher
+    }
+
+    /// Sets how often the files are checked for changes
+    #[must_use]
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Sets how long no further changes must occur after a change before the project is
+    /// recompiled
+    #[must_use]
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Returns the watched project
+    pub fn project(&self) -> &Project<C, T> {
+        &self.project
+    }
+
+    /// Returns the watched project
+    pub fn into_project(self) -> Project<C, T> {
+        self.project
+    }
+
+    /// Returns the path of the remappings file
+    pub fn remappings_file(&self) -> PathBuf {
+        self.project.root().join(REMAPPINGS_FILE)
+    }
+
+    /// Compiles the project and then recompiles it on every change, until `on_output` returns
+    /// [`ControlFlow::Break`].
+    ///
+    /// Changes that don't affect any file of the project's graph, like adding a library file that
+    /// isn't imported, don't trigger a recompilation.
+    pub fn watch<F>(mut self, mut on_output: F)
+    where
+        F: FnMut(WatchOutput<C::CompilationError, T>) -> ControlFlow<()>,
+    {
+        let mut changes = FileChanges::default();
+        loop {
+            if let Some(output) = self.recompile(changes) {
+                if on_output(output).is_break() {
+                    return;
+                }
+            }
+            changes = self.wait_for_changes();
+        }
+    }
+
+    /// Same as [`Self::watch()`] but sends every output to the channel, until the receiver is
+    /// dropped.
+    ///
+    /// Since the watcher blocks the current thread, this is typically called from a separate
+    /// thread. Note that a dropped receiver is only noticed on the next recompilation.
+    pub fn watch_channel(self, sender: mpsc::Sender<WatchOutput<C::CompilationError, T>>) {
+        self.watch(|output| match sender.send(output) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(_) => ControlFlow::Break(()),
+        })
+    }
+
+    /// Returns the changes since the last check without waiting, or `None` if nothing changed
+    pub fn poll(&mut self) -> Option<FileChanges> {
+        let current = self.snapshot();
+        let changes = FileChanges::new(&self.snapshot, &current, &self.remappings_file());
+        self.snapshot = current;
+        (!changes.is_empty()).then_some(changes)
+    }
+
+    /// Blocks until files change and no further changes occurred for the debounce duration, and
+    /// returns all changes since the last check.
+    pub fn wait_for_changes(&mut self) -> FileChanges {
+        loop {
+            thread::sleep(self.poll_interval);
+            let mut current = self.snapshot();
+            if current == self.snapshot {
+                continue;
+            }
+
+            let mut last_change = Instant::now();
+            while last_change.elapsed() < self.debounce {
+                thread::sleep(
+                    self.poll_interval.min(self.debounce.saturating_sub(last_change.elapsed())),
+                );
+                let next = self.snapshot();
+                if next != current {
+                    current = next;
+                    last_change = Instant::now();
+                }
+            }
+
+            let changes = FileChanges::new(&self.snapshot, &current, &self.remappings_file());
+            self.snapshot = current;
+            // files may have been changed and restored within the debounce duration
+            if !changes.is_empty() {
+                return changes;
+            }
+        }
+    }
+
+    /// Applies the changes to the project and returns all affected files, together with the
+    /// resolved graph of the project.
+    ///
+    /// This reloads the remappings if the remappings file changed and resolves the graph of the
+    /// project. The affected files are the added and modified files and all files that directly or
+    /// transitively import them, as well as the removed files that were part of the graph and
+    /// their importers. If the remappings changed, every file of the
+    /// graph is affected, since imports may resolve to different files.
+    ///
+    /// Files that became part of the graph, like newly imported library files, and the files of
+    /// newly remapped directories are watched from now on.
+    fn update(
+        &mut self,
+        changes: &FileChanges,
+    ) -> Result<(BTreeSet<PathBuf>, Graph<C::ParsedSource>)> {
+        if changes.remappings_changed {
+            self.reload_remappings();
+        }
+
+        let graph = Graph::<C::ParsedSource>::resolve(&self.project.paths)?;
+        let mut affected: BTreeSet<_> = if changes.remappings_changed {
+            graph.files().keys().cloned().collect()
+        } else {
+            graph
+                .affected_contracts(changes.added.iter().chain(&changes.modified))
+                .into_keys()
+                .collect()
+        };
+        for removed in changes.removed.iter().filter(|f| self.importers.contains_key(*f)) {
+            affected.insert(removed.clone());
+            // all files that transitively imported the removed file and are still part of the graph
+            let mut queue = vec![removed];
+            let mut visited = BTreeSet::new();
+            while let Some(file) = queue.pop() {
+                for importer in self.importers.get(file).into_iter().flatten() {
+                    if visited.insert(importer) {
+                        queue.push(importer);
+                    }
+                }
+            }
+            affected.extend(
+                visited.into_iter().filter(|file| graph.files().contains_key(*file)).cloned(),
+            );
+        }
+
+        self.importers = importers(&graph);
+        for (file, stamp) in self.snapshot() {
+            self.snapshot.entry(file).or_insert(stamp);
+        }
+        Ok((affected, graph))
+    }
+
+    /// Updates the project and recompiles it from the resolved graph, returns `None` if the changes
+    /// don't affect the project.
+    ///
+    /// The affected files only decide whether to recompile, the cache decides which files are
+    /// rebuilt.
+    fn recompile(&mut self, changes: FileChanges) -> Option<WatchOutput<C::CompilationError, T>> {
+        let output = match self.update(&changes) {
+            Ok((affected, _)) if affected.is_empty() && !changes.is_empty() => {
+                trace!("changes don't affect the project: {:?}", changes);
+                return None;
+            }
+            Ok((affected, graph)) => {
+                trace!("recompiling after changes that affect {} files", affected.len());
+                ProjectCompiler::with_graph(&self.project, graph).and_then(ProjectCompiler::compile)
+            }
+            Err(err) => Err(err),
+        };
+        Some(WatchOutput { changes, output })
+    }
+
+    /// Replaces the remappings of the project with the remappings declared in the remappings file,
+    /// followed by the remappings the project was configured with.
+    fn reload_remappings(&mut self) {
+        let mut remappings = self.read_remappings_file();
+        remappings.extend(self.configured_remappings.iter().cloned());
+        trace!("reloaded remappings: {:?}", remappings);
+        self.project.paths.remappings = remappings;
+    }
+
+    /// Reads the remappings declared in the remappings file, relative to the project root
+    fn read_remappings_file(&self) -> Vec<Remapping> {
+        let file = self.remappings_file();
+        let Ok(content) = fs::read_to_string(&file) else { return Vec::new() };
+        let root = self.project.root();
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| match Remapping::from_str(line) {
+                Ok(remapping) => Some(remapping.into_relative(root).to_remapping(root.clone())),
+                Err(err) => {
+                    warn!("{err} in \"{}\"", file.display());
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the current state of all watched files.
+    ///
+    /// These are the files in the sources, tests and scripts directories and in the directories the
+    /// remappings point to, the files of the last resolved graph, which includes the imported
+    /// library files, and the remappings file.
+    fn snapshot(&self) -> Snapshot {
+        let paths = &self.project.paths;
+        let remapped = paths.remappings.iter().map(|remapping| Path::new(&remapping.path));
+        [&paths.sources, &paths.tests, &paths.scripts]
+            .into_iter()
+            .map(PathBuf::as_path)
+            .chain(remapped)
+            .flat_map(|dir| utils::source_files_iter(dir, C::FILE_EXTENSIONS))
+            .chain(self.importers.keys().cloned())
+            .chain(Some(self.remappings_file()))
+            .filter_map(|file| Some((file.clone(), FileStamp::read(&file)?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CompilerConfig, ProjectPathsConfig};
+    use semver::Version;
+
+    #[test]
+    fn can_detect_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = utils::canonicalized(tmp.path());
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("lib/dep")).unwrap();
+        fs::write(root.join("src/A.sol"), "import \"./B.sol\";\ncontract A {}").unwrap();
+        fs::write(root.join("src/B.sol"), "contract B {}").unwrap();
+        fs::write(root.join("src/C.sol"), "contract C {}").unwrap();
+        fs::write(root.join("lib/dep/Dep.sol"), "contract Dep {}").unwrap();
+
+        let paths = ProjectPathsConfig::builder()
+            .root(&root)
+            .sources(root.join("src"))
+            .lib(root.join("lib"))
+            .remappings(Vec::new())
+            .build::<Solc>()
+            .unwrap();
+        let solc = Solc::new_with_version("solc", Version::new(0, 8, 19));
+        let project = Project::builder()
+            .paths(paths)
+            .ephemeral()
+            .no_artifacts()
+            .build(CompilerConfig::Specific(solc))
+            .unwrap();
+        let mut watcher = ProjectWatcher::new(project);
+        assert!(watcher.update(&FileChanges::default()).unwrap().0.is_empty());
+        assert_eq!(watcher.poll(), None);
+
+        fs::write(root.join("src/B.sol"), "contract B { uint x; }").unwrap();
+        fs::write(root.join("src/D.sol"), "contract D {}").unwrap();
+        let changes = watcher.poll().unwrap();
+        assert_eq!(changes.modified, BTreeSet::from([root.join("src/B.sol")]));
+        assert_eq!(changes.added, BTreeSet::from([root.join("src/D.sol")]));
+        assert_eq!(
+            watcher.update(&changes).unwrap().0,
+            BTreeSet::from([
+                root.join("src/A.sol"),
+                root.join("src/B.sol"),
+                root.join("src/D.sol")
+            ])
+        );
+
+        // library files outside of remapped directories are only watched once they're imported
+        fs::write(root.join("lib/dep/Dep.sol"), "contract Dep { uint x; }").unwrap();
+        assert_eq!(watcher.poll(), None);
+        fs::write(root.join("src/C.sol"), "import \"../lib/dep/Dep.sol\";\ncontract C {}").unwrap();
+        let changes = watcher.poll().unwrap();
+        assert_eq!(watcher.update(&changes).unwrap().0, BTreeSet::from([root.join("src/C.sol")]));
+        assert_eq!(watcher.poll(), None);
+        fs::write(root.join("lib/dep/Dep.sol"), "contract Dep { uint y; }").unwrap();
+        let changes = watcher.poll().unwrap();
+        assert_eq!(changes.modified, BTreeSet::from([root.join("lib/dep/Dep.sol")]));
+        assert_eq!(
+            watcher.update(&changes).unwrap().0,
+            BTreeSet::from([root.join("lib/dep/Dep.sol"), root.join("src/C.sol")])
+        );
+
+        fs::write(root.join("src/A.sol"), "contract A {}").unwrap();
+        fs::remove_file(root.join("src/B.sol")).unwrap();
+        let changes = watcher.poll().unwrap();
+        assert_eq!(changes.removed, BTreeSet::from([root.join("src/B.sol")]));
+        assert_eq!(
+            watcher.update(&changes).unwrap().0,
+            BTreeSet::from([root.join("src/A.sol"), root.join("src/B.sol")])
+        );
+
+        fs::write(root.join(REMAPPINGS_FILE), "dep/=lib/dep/\n# comment\n").unwrap();
+        let changes = watcher.poll().unwrap();
+        assert!(changes.remappings_changed);
+        assert_eq!(changes.files().count(), 0);
+        assert_eq!(watcher.update(&changes).unwrap().0.len(), 4);
+        assert_eq!(
+            watcher.project().paths.remappings,
+            vec![Remapping {
+                context: None,
+                name: "dep/".to_string(),
+                path: format!("{}/", root.join("lib/dep").display()),
+            }]
+        );
+
+        // the directories the remappings point to are watched
+        fs::write(root.join("lib/dep/Other.sol"), "contract Other {}").unwrap();
+        let changes = watcher.poll().unwrap();
+        assert_eq!(changes.added, BTreeSet::from([root.join("lib/dep/Other.sol")]));
+        assert!(watcher.update(&changes).unwrap().0.is_empty());
+    }
+}
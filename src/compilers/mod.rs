@@ -70,6 +70,11 @@ pub trait ParsedSource: Debug + Sized + Send {
     fn parse(content: &str, file: &Path) -> Self;
     fn version_req(&self) -> Option<&VersionReq>;
     fn resolve_imports<C>(&self, paths: &ProjectPathsConfig<C>) -> Result<Vec<PathBuf>>;
+
+    /// Returns the names of all contracts defined in the source.
+    fn contract_names(&self) -> Vec<String> {
+        Vec::new()
+    }
 }
 
 /// Error returned by compiler. Might also represent a warning or informational message.
@@ -179,6 +179,10 @@ impl ParsedSource for SolData {
     fn resolve_imports<C>(&self, _paths: &crate::ProjectPathsConfig<C>) -> Result<Vec<PathBuf>> {
         return Ok(self.imports.iter().map(|i| i.data().path().to_path_buf()).collect_vec());
     }
+
+    fn contract_names(&self) -> Vec<String> {
+        self.contracts.iter().map(|contract| contract.name.clone()).collect()
+    }
 }
 
 impl CompilationError for Error {
@@ -45,6 +45,11 @@ impl ParsedSource for VyperParsedSource {
         self.version_req.as_ref()
     }
 
+    /// Vyper contracts are named after their file
+    fn contract_names(&self) -> Vec<String> {
+        self.path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).into_iter().collect()
+    }
+
     fn resolve_imports<C>(&self, paths: &ProjectPathsConfig<C>) -> Result<Vec<PathBuf>> {
         let mut imports = Vec::new();
         'outer: for import in &self.imports {
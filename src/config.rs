@@ -3,9 +3,9 @@ use crate::{
     cache::SOLIDITY_FILES_CACHE_FILENAME,
     compilers::Compiler,
     error::{Result, SolcError, SolcIoError},
-    flatten::{collect_ordered_deps, combine_version_pragmas},
+    flatten::{collect_ordered_deps, combine_version_pragmas, replace_import_aliases},
     remappings::Remapping,
-    resolver::{Graph, SolImportAlias},
+    resolver::{Graph, ImportResolver, ImportResolverChain},
     utils, Solc, Source, Sources,
 };
 use serde::{Deserialize, Serialize};
@@ -42,6 +42,9 @@ pub struct ProjectPathsConfig<C = Solc> {
     pub include_paths: BTreeSet<PathBuf>,
     /// The paths which will be allowed for library inclusion
     pub allowed_paths: BTreeSet<PathBuf>,
+    /// Custom import resolvers which are consulted before the built-in import resolution
+    #[serde(skip)]
+    pub import_resolvers: ImportResolverChain,
 
     pub _c: PhantomData<C>,
 }
@@ -131,29 +134,7 @@ impl ProjectPathsConfig {
                 SolcError::msg(format!("failed to convert extended bytes to string: {err}"))
             })?;
 
-            // Iterate over all aliased imports, and replace alias with real name via regexes
-            for alias in node.data.imports.iter().flat_map(|i| i.data().aliases()) {
-                let (alias, target) = match alias {
-                    SolImportAlias::Contract(alias, target) => (alias.clone(), target.clone()),
-                    _ => continue,
-                };
-                let name_regex = utils::create_contract_or_lib_name_regex(&alias);
-                let target_len = target.len() as isize;
-                let mut replace_offset = 0;
-                for cap in name_regex.captures_iter(&content.clone()) {
-                    if cap.name("ignore").is_some() {
-                        continue;
-                    }
-                    if let Some(name_match) =
-                        ["n1", "n2", "n3"].iter().find_map(|name| cap.name(name))
-                    {
-                        let name_match_range =
-                            utils::range_by_offset(&name_match.range(), replace_offset);
-                        replace_offset += target_len - (name_match_range.len() as isize);
-                        content.replace_range(name_match_range, &target);
-                    }
-                }
-            }
+            replace_import_aliases(&mut content, &node.data.imports);
 
             let content = format!(
                 "// {}\n{}",
@@ -365,51 +346,83 @@ impl<C> ProjectPathsConfig<C> {
         import: &Path,
         include_paths: &mut BTreeSet<PathBuf>,
     ) -> Result<PathBuf> {
-        let component = import
-            .components()
-            .next()
-            .ok_or_else(|| SolcError::msg(format!("Empty import path {}", import.display())))?;
+        self.resolve_import_with_steps(cwd, import, include_paths, None)
+    }
+
+    /// Resolves the `import` like [`Self::resolve_import_and_include_paths()`] and records every
+    /// step of the resolution in `steps`, if any.
+    fn resolve_import_with_steps(
+        &self,
+        cwd: &Path,
+        import: &Path,
+        include_paths: &mut BTreeSet<PathBuf>,
+        mut steps: Option<&mut Vec<ImportResolutionStep>>,
+    ) -> Result<PathBuf> {
+        let Some(component) = import.components().next() else {
+            record_step(&mut steps, || ImportResolutionStep::EmptyImport);
+            return Err(SolcError::msg(format!("Empty import path {}", import.display())));
+        };
 
         if component == Component::CurDir || component == Component::ParentDir {
             // if the import is relative we assume it's already part of the processed input
             // file set
-            utils::normalize_solidity_import_path(cwd, import).map_err(|err| {
+            let resolved = utils::normalize_solidity_import_path(cwd, import);
+            record_step(&mut steps, || {
+                let (candidate, exists) = probed_candidate(cwd, import, &resolved);
+                ImportResolutionStep::Relative { candidate, exists }
+            });
+            return resolved.map_err(|err| {
                 SolcError::msg(format!("failed to resolve relative import \"{err:?}\""))
-            })
-        } else {
-            // resolve library file
-            let resolved = self.resolve_library_import(cwd.as_ref(), import.as_ref());
-
-            if resolved.is_none() {
-                // absolute paths in solidity are a thing for example `import
-                // "src/interfaces/IConfig.sol"` which could either point to `cwd +
-                // src/interfaces/IConfig.sol`, or make use of a remapping (`src/=....`)
-                if let Some(lib) = self.find_library_ancestor(cwd) {
-                    if let Some((include_path, import)) =
-                        utils::resolve_absolute_library(lib, cwd, import)
-                    {
-                        // track the path for this absolute import inside a nested library
-                        include_paths.insert(include_path);
-                        return Ok(import);
-                    }
-                }
-                // also try to resolve absolute imports from the project paths
-                for path in [&self.root, &self.sources, &self.tests, &self.scripts] {
-                    if cwd.starts_with(path) {
-                        if let Ok(import) = utils::normalize_solidity_import_path(path, import) {
-                            return Ok(import);
-                        }
+            });
+        }
+
+        // resolve library file
+        if let Some(resolved) =
+            self.resolve_library_import_with_steps(cwd, import, steps.as_deref_mut())
+        {
+            return Ok(resolved);
+        }
+
+        // absolute paths in solidity are a thing for example `import
+        // "src/interfaces/IConfig.sol"` which could either point to `cwd +
+        // src/interfaces/IConfig.sol`, or make use of a remapping (`src/=....`), see also
+        // `utils::resolve_absolute_library`
+        if let Some(lib) = self.find_library_ancestor(cwd) {
+            let mut parent = cwd.parent();
+            while let Some(include_path) = parent.filter(|parent| *parent != lib) {
+                let resolved = utils::normalize_solidity_import_path(include_path, import);
+                record_step(&mut steps, || {
+                    let (candidate, exists) = probed_candidate(include_path, import, &resolved);
+                    ImportResolutionStep::IncludePath {
+                        include_path: include_path.to_path_buf(),
+                        candidate,
+                        exists,
                     }
+                });
+                if let Ok(import) = resolved {
+                    // track the path for this absolute import inside a nested library
+                    include_paths.insert(include_path.to_path_buf());
+                    return Ok(import);
                 }
+                parent = include_path.parent();
             }
+        }
 
-            resolved.ok_or_else(|| {
-                SolcError::msg(format!(
-                    "failed to resolve library import \"{:?}\"",
-                    import.display()
-                ))
-            })
+        // also try to resolve absolute imports from the project paths
+        for path in [&self.root, &self.sources, &self.tests, &self.scripts] {
+            if cwd.starts_with(path) {
+                let resolved = utils::normalize_solidity_import_path(path, import);
+                record_step(&mut steps, || {
+                    let (candidate, exists) = probed_candidate(path, import, &resolved);
+                    ImportResolutionStep::ProjectPath { path: path.clone(), candidate, exists }
+                });
+                if let Ok(import) = resolved {
+                    return Ok(import);
+                }
+            }
         }
+
+        Err(SolcError::msg(format!("failed to resolve library import \"{:?}\"", import.display())))
     }
 
     /// Attempts to resolve an `import` from the given working directory.
@@ -469,41 +482,192 @@ impl<C> ProjectPathsConfig<C> {
     /// `@openzeppelin/contracts/contracts/token/ERC20/IERC20.sol` we check for this edge case
     /// here so that both styles work out of the box.
     pub fn resolve_library_import(&self, cwd: &Path, import: &Path) -> Option<PathBuf> {
+        self.resolve_library_import_with_steps(cwd, import, None)
+    }
+
+    /// Resolves the `import` like [`Self::resolve_library_import()`] and records every step of the
+    /// resolution in `steps`, if any.
+    fn resolve_library_import_with_steps(
+        &self,
+        cwd: &Path,
+        import: &Path,
+        mut steps: Option<&mut Vec<ImportResolutionStep>>,
+    ) -> Option<PathBuf> {
         // if the import path starts with the name of the remapping then we get the resolved path by
-        // removing the name and adding the remainder to the path of the remapping
-        let cwd = cwd.strip_prefix(&self.root).unwrap_or(cwd);
-        if let Some(path) = self
-            .remappings
-            .iter()
-            .filter(|r| {
-                // only check remappings that are either global or for `cwd`
-                if let Some(ctx) = r.context.as_ref() {
-                    cwd.starts_with(ctx)
-                } else {
-                    true
+        // removing the name and adding the remainder to the path of the remapping, the first
+        // matching remapping wins, even if the remapped file doesn't exist
+        let relative_cwd = cwd.strip_prefix(&self.root).unwrap_or(cwd);
+        for remapping in &self.remappings {
+            let outcome = if !remapping_applies_to(remapping, relative_cwd) {
+                RemappingOutcome::ContextMismatch
+            } else if let Some(path) = apply_remapping(remapping, import) {
+                let candidate = self.root.join(path);
+                record_step(&mut steps, || ImportResolutionStep::Remapping {
+                    remapping: remapping.clone(),
+                    outcome: RemappingOutcome::Matched {
+                        candidate: candidate.clone(),
+                        exists: candidate.exists(),
+                    },
+                });
+                return Some(candidate);
+            } else {
+                RemappingOutcome::PrefixMismatch
+            };
+            record_step(&mut steps, || ImportResolutionStep::Remapping {
+                remapping: remapping.clone(),
+                outcome,
+            });
+        }
+
+        // see `utils::resolve_library`
+        match import.components().next()? {
+            Component::Normal(first_dir) => {
+                for lib in &self.libraries {
+                    // check for <lib>/<import> and <lib>/<first_dir>/src/<rest of import>
+                    let nested = lib
+                        .join(first_dir)
+                        .join("src")
+                        .join(import.strip_prefix(first_dir).expect("is first component"));
+                    for candidate in [lib.join(import), nested] {
+                        let exists = candidate.exists();
+                        record_step(&mut steps, || ImportResolutionStep::Library {
+                            lib: lib.clone(),
+                            candidate: candidate.clone(),
+                            exists,
+                        });
+                        if exists {
+                            return Some(candidate);
+                        }
+                    }
                 }
-            })
-            .find_map(|r| {
-                import.strip_prefix(&r.name).ok().map(|stripped_import| {
-                    let lib_path = Path::new(&r.path).join(stripped_import);
-
-                    // we handle the edge case where the path of a remapping ends with "contracts"
-                    // (`<name>/=.../contracts`) and the stripped import also starts with
-                    // `contracts`
-                    if let Ok(adjusted_import) = stripped_import.strip_prefix("contracts/") {
-                        if r.path.ends_with("contracts/") && !lib_path.exists() {
-                            return Path::new(&r.path).join(adjusted_import);
+                None
+            }
+            Component::RootDir => {
+                record_step(&mut steps, || ImportResolutionStep::Absolute {
+                    exists: import.exists(),
+                });
+                Some(import.to_path_buf())
+            }
+            _ => None,
+        }
+    }
+
+    /// Explains how the `import` of the `importing_file` is resolved.
+    ///
+    /// This returns every step of [`Self::resolve_import_and_include_paths()`] in order, including
+    /// the custom import resolvers that are consulted first by the [`Graph`]: all remappings that
+    /// did or didn't match, all lib dirs and include paths that were probed, and the canonicalized
+    /// path that was finally chosen, if any.
+    ///
+    /// Relative `importing_file` paths are relative to the project root.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use foundry_compilers::ProjectPathsConfig;
+    ///
+    /// let config: ProjectPathsConfig = ProjectPathsConfig::dapptools(".")?;
+    /// println!("{}", config.explain_import("src/Counter.sol", "forge-std/Test.sol"));
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn explain_import(
+        &self,
+        importing_file: impl AsRef<Path>,
+        import: impl AsRef<Path>,
+    ) -> ImportExplanation {
+        let importing_file = self.root.join(importing_file);
+        let import = import.as_ref();
+        let mut steps = Vec::new();
+        let resolved = importing_file
+            .parent()
+            .and_then(|cwd| {
+                if self.consults_import_resolvers(import) {
+                    match self.import_resolvers.resolve_import(cwd, import) {
+                        Ok(None) => steps.push(ImportResolutionStep::ImportResolvers {
+                            resolved: None,
+                            error: None,
+                        }),
+                        Ok(Some(resolved)) => {
+                            steps.push(ImportResolutionStep::ImportResolvers {
+                                resolved: Some(resolved.clone()),
+                                error: None,
+                            });
+                            return Some(resolved);
+                        }
+                        Err(err) => {
+                            steps.push(ImportResolutionStep::ImportResolvers {
+                                resolved: None,
+                                error: Some(err.to_string()),
+                            });
+                            return None;
                         }
                     }
-                    lib_path
-                })
+                }
+                self.resolve_import_with_steps(
+                    cwd,
+                    import,
+                    &mut Default::default(),
+                    Some(&mut steps),
+                )
+                .ok()
             })
-        {
-            Some(self.root.join(path))
-        } else {
-            utils::resolve_library(&self.libraries, import)
+            .filter(|resolved| resolved.exists())
+            .map(utils::canonicalized);
+        ImportExplanation { importing_file, import: import.to_path_buf(), steps, resolved }
+    }
+
+    /// Returns `true` if the custom import resolvers are consulted for the `import`, before the
+    /// remappings and lib dirs.
+    ///
+    /// Relative imports are always resolved relative to the importing file by solc.
+    pub(crate) fn consults_import_resolvers(&self, import: &Path) -> bool {
+        !self.import_resolvers.is_empty()
+            && !matches!(import.components().next(), Some(Component::CurDir | Component::ParentDir))
+    }
+}
+
+/// Records the step in `steps`, if any, see [`ProjectPathsConfig::explain_import()`].
+fn record_step(
+    steps: &mut Option<&mut Vec<ImportResolutionStep>>,
+    step: impl FnOnce() -> ImportResolutionStep,
+) {
+    if let Some(steps) = steps {
+        steps.push(step());
+    }
+}
+
+/// Returns the probed candidate for the `import` in `dir` and whether it exists, given the result of
+/// [`utils::normalize_solidity_import_path()`].
+fn probed_candidate(
+    dir: &Path,
+    import: &Path,
+    resolved: &std::result::Result<PathBuf, SolcIoError>,
+) -> (PathBuf, bool) {
+    match resolved {
+        Ok(path) => (path.clone(), true),
+        Err(_) => (dir.join(import), false),
+    }
+}
+
+/// Returns `true` if the remapping is either global or its context contains `cwd`.
+pub(crate) fn remapping_applies_to(remapping: &Remapping, cwd: &Path) -> bool {
+    remapping.context.as_ref().map_or(true, |ctx| cwd.starts_with(ctx))
+}
+
+/// Applies the remapping to the import, returning `None` if the import doesn't start with the name
+/// of the remapping.
+pub(crate) fn apply_remapping(remapping: &Remapping, import: &Path) -> Option<PathBuf> {
+    let stripped_import = import.strip_prefix(&remapping.name).ok()?;
+    let lib_path = Path::new(&remapping.path).join(stripped_import);
+
+    // we handle the edge case where the path of a remapping ends with "contracts"
+    // (`<name>/=.../contracts`) and the stripped import also starts with `contracts`
+    if let Ok(adjusted_import) = stripped_import.strip_prefix("contracts/") {
+        if remapping.path.ends_with("contracts/") && !lib_path.exists() {
+            return Some(Path::new(&remapping.path).join(adjusted_import));
         }
     }
+    Some(lib_path)
 }
 
 impl<C: Compiler> ProjectPathsConfig<C> {
@@ -571,6 +735,121 @@ impl fmt::Display for ProjectPathsConfig {
     }
 }
 
+/// Explanation of how an import is resolved, see [`ProjectPathsConfig::explain_import()`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportExplanation {
+    /// The file that contains the import
+    pub importing_file: PathBuf,
+    /// The import path as written in the importing file
+    pub import: PathBuf,
+    /// All resolution steps, in the order they were tried
+    pub steps: Vec<ImportResolutionStep>,
+    /// The canonicalized path the import resolves to, if any
+    pub resolved: Option<PathBuf>,
+}
+
+impl fmt::Display for ImportExplanation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "resolving \"{}\" imported by {}:",
+            self.import.display(),
+            self.importing_file.display()
+        )?;
+        for step in &self.steps {
+            writeln!(f, "    {step}")?;
+        }
+        match &self.resolved {
+            Some(resolved) => write!(f, "resolved to {}", resolved.display()),
+            None => write!(f, "failed to resolve import"),
+        }
+    }
+}
+
+/// A single step of the import resolution, see [`ImportExplanation`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportResolutionStep {
+    /// The import path is empty
+    EmptyImport,
+    /// A relative import was resolved relative to the directory of the importing file
+    Relative { candidate: PathBuf, exists: bool },
+    /// The custom import resolvers were consulted
+    ImportResolvers { resolved: Option<PathBuf>, error: Option<String> },
+    /// A remapping was checked
+    Remapping { remapping: Remapping, outcome: RemappingOutcome },
+    /// A candidate in a lib dir was probed
+    Library { lib: PathBuf, candidate: PathBuf, exists: bool },
+    /// The import is an absolute path
+    Absolute { exists: bool },
+    /// An absolute import in a nested library was probed relative to a parent directory of the
+    /// importing file, which would be passed to solc as `--include-path`
+    IncludePath { include_path: PathBuf, candidate: PathBuf, exists: bool },
+    /// The import was probed relative to a project directory
+    ProjectPath { path: PathBuf, candidate: PathBuf, exists: bool },
+}
+
+/// The outcome of checking a remapping, see [`ImportResolutionStep::Remapping`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RemappingOutcome {
+    /// The remapping has a context that doesn't contain the importing file
+    ContextMismatch,
+    /// The import doesn't start with the name of the remapping
+    PrefixMismatch,
+    /// The remapping matched, which determines the resolved path even if it doesn't exist
+    Matched { candidate: PathBuf, exists: bool },
+}
+
+impl fmt::Display for ImportResolutionStep {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let found = |exists: &bool| if *exists { "found" } else { "not found" };
+        match self {
+            Self::EmptyImport => write!(f, "empty import path"),
+            Self::Relative { candidate, exists } => {
+                write!(f, "relative import {}: {}", candidate.display(), found(exists))
+            }
+            Self::ImportResolvers { resolved: Some(resolved), .. } => {
+                write!(f, "custom import resolvers: resolved to {}", resolved.display())
+            }
+            Self::ImportResolvers { error: Some(error), .. } => {
+                write!(f, "custom import resolvers: {error}")
+            }
+            Self::ImportResolvers { .. } => write!(f, "custom import resolvers: not handled"),
+            Self::Remapping { remapping, outcome } => match outcome {
+                RemappingOutcome::ContextMismatch => {
+                    write!(f, "remapping {remapping}: context doesn't apply to the importing file")
+                }
+                RemappingOutcome::PrefixMismatch => {
+                    write!(f, "remapping {remapping}: import doesn't match the prefix")
+                }
+                RemappingOutcome::Matched { candidate, exists } => write!(
+                    f,
+                    "remapping {remapping}: matched {}: {}",
+                    candidate.display(),
+                    found(exists)
+                ),
+            },
+            Self::Library { lib, candidate, exists } => {
+                write!(f, "lib {}: {}: {}", lib.display(), candidate.display(), found(exists))
+            }
+            Self::Absolute { exists } => write!(f, "absolute import: {}", found(exists)),
+            Self::IncludePath { include_path, candidate, exists } => write!(
+                f,
+                "include path {}: {}: {}",
+                include_path.display(),
+                candidate.display(),
+                found(exists)
+            ),
+            Self::ProjectPath { path, candidate, exists } => write!(
+                f,
+                "project path {}: {}: {}",
+                path.display(),
+                candidate.display(),
+                found(exists)
+            ),
+        }
+    }
+}
+
 /// This is a subset of [ProjectPathsConfig] that contains all relevant folders in the project
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ProjectPaths {
@@ -683,6 +962,7 @@ pub struct ProjectPathsConfigBuilder {
     remappings: Option<Vec<Remapping>>,
     include_paths: BTreeSet<PathBuf>,
     allowed_paths: BTreeSet<PathBuf>,
+    import_resolvers: ImportResolverChain,
 }
 
 impl ProjectPathsConfigBuilder {
@@ -789,6 +1069,14 @@ impl ProjectPathsConfigBuilder {
         self
     }
 
+    /// Adds a custom import resolver, see [`ImportResolver`]
+    ///
+    /// Resolvers are consulted in the order they were added.
+    pub fn import_resolver(mut self, resolver: impl ImportResolver + 'static) -> Self {
+        self.import_resolvers.push(resolver);
+        self
+    }
+
     pub fn build_with_root<C>(self, root: impl Into<PathBuf>) -> ProjectPathsConfig<C> {
         let root = utils::canonicalized(root);
 
@@ -816,6 +1104,7 @@ impl ProjectPathsConfigBuilder {
             root,
             include_paths: self.include_paths,
             allowed_paths,
+            import_resolvers: self.import_resolvers,
             _c: PhantomData,
         }
     }
@@ -1074,4 +1363,69 @@ mod tests {
             dependency.join("A.sol")
         );
     }
+
+    #[test]
+    fn can_explain_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = ProjectPathsConfig::builder()
+            .root(dir.path())
+            .lib(dir.path().join("lib"))
+            .build::<()>()
+            .unwrap();
+        config.create_all().unwrap();
+
+        let lib = config.root.join("lib");
+        fs::create_dir_all(lib.join("dep/src")).unwrap();
+        fs::write(lib.join("dep/src/Dep.sol"), "contract Dep {}").unwrap();
+        let test_remapping: Remapping = "test/:dep/=lib/other/".parse().unwrap();
+        let other_remapping: Remapping = "other/=lib/other/".parse().unwrap();
+        config.remappings = vec![test_remapping.clone(), other_remapping.clone()];
+
+        let importing_file = config.sources.join("A.sol");
+        let explanation = config.explain_import(&importing_file, "dep/Dep.sol");
+        assert_eq!(
+            explanation.steps,
+            vec![
+                ImportResolutionStep::Remapping {
+                    remapping: test_remapping,
+                    outcome: RemappingOutcome::ContextMismatch
+                },
+                ImportResolutionStep::Remapping {
+                    remapping: other_remapping,
+                    outcome: RemappingOutcome::PrefixMismatch
+                },
+                ImportResolutionStep::Library {
+                    lib: lib.clone(),
+                    candidate: lib.join("dep/Dep.sol"),
+                    exists: false
+                },
+                ImportResolutionStep::Library {
+                    lib: lib.clone(),
+                    candidate: lib.join("dep/src/Dep.sol"),
+                    exists: true
+                },
+            ]
+        );
+        assert_eq!(
+            explanation.resolved,
+            Some(utils::canonicalized(
+                config.resolve_import(&config.sources, Path::new("dep/Dep.sol")).unwrap()
+            ))
+        );
+
+        let explanation = config.explain_import("src/A.sol", "other/Missing.sol");
+        assert_eq!(explanation.importing_file, importing_file);
+        assert_eq!(
+            explanation.steps[1],
+            ImportResolutionStep::Remapping {
+                remapping: "other/=lib/other/".parse().unwrap(),
+                outcome: RemappingOutcome::Matched {
+                    candidate: config.root.join("lib/other/Missing.sol"),
+                    exists: false
+                }
+            }
+        );
+        assert_eq!(explanation.resolved, None);
+        assert!(explanation.to_string().ends_with("failed to resolve import"), "{explanation}");
+    }
 }
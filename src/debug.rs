@@ -0,0 +1,344 @@
+//! Debug information that maps the program counters of bytecode to source locations.
+
+use crate::{
+    artifacts::{
+        BytecodeObject, CompactBytecode, CompactDeployedBytecode, GeneratedSource, Source,
+    },
+    error::{Result, SolcError},
+    sourcemap::{SourceElement, SourceMap, SyntaxError},
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+/// Returns the number of data bytes that follow the opcode.
+///
+/// Only `PUSH1` to `PUSH32` are followed by data.
+pub(crate) fn push_size(opcode: u8) -> usize {
+    match opcode {
+        0x60..=0x7f => (opcode - 0x5f) as usize,
+        _ => 0,
+    }
+}
+
+/// Returns the program counter of every instruction of the bytecode.
+///
+/// The data of `PUSH` instructions is skipped, so the n-th entry is the program counter of the n-th
+/// instruction, which is described by the n-th element of the source map.
+pub fn instruction_pcs(code: &[u8]) -> Vec<usize> {
+    let mut pcs = Vec::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        pcs.push(pc);
+        pc += 1 + push_size(code[pc]);
+    }
+    pcs
+}
+
+/// A source file that's referenced by the source index of a source map.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DebugSource {
+    /// The path of the file, or the name of a generated source like `#utility.yul`
+    pub path: PathBuf,
+    /// The content of the file
+    pub content: String,
+    /// Whether this is a virtual file with code generated by the compiler
+    pub generated: bool,
+    /// The offsets of the first byte of every line
+    line_starts: Vec<usize>,
+}
+
+impl DebugSource {
+    pub fn new(path: impl Into<PathBuf>, content: impl Into<String>) -> Self {
+        let content = content.into();
+        let line_starts =
+            std::iter::once(0).chain(content.match_indices('\n').map(|(idx, _)| idx + 1)).collect();
+        Self { path: path.into(), content, generated: false, line_starts }
+    }
+
+    /// Creates a virtual file from a Yul source generated by the compiler
+    pub fn generated(source: &GeneratedSource) -> Self {
+        Self { generated: true, ..Self::new(&source.name, source.contents.as_str()) }
+    }
+
+    /// Returns the 1-based line and column of the byte offset, columns are counted in bytes.
+    pub fn line_column(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        (line, offset - self.line_starts[line - 1] + 1)
+    }
+}
+
+/// The source location of an instruction, see [`DebugInfo::resolve()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedLocation<'a> {
+    /// The program counter of the instruction
+    pub pc: usize,
+    /// The index of the instruction in the bytecode and the source map
+    pub instruction: usize,
+    /// The source map element of the instruction
+    pub element: &'a SourceElement,
+    /// The source file of the instruction
+    pub source: &'a DebugSource,
+    /// The byte range of the instruction in the source file
+    pub range: Range<usize>,
+    /// The 1-based line of the start of the range
+    pub line: usize,
+    /// The 1-based column of the start of the range, counted in bytes
+    pub column: usize,
+}
+
+impl<'a> ResolvedLocation<'a> {
+    /// Returns the path of the source file
+    pub fn path(&self) -> &'a Path {
+        &self.source.path
+    }
+
+    /// Returns the source code of the instruction
+    pub fn snippet(&self) -> &'a str {
+        self.source.content.get(self.range.clone()).unwrap_or_default()
+    }
+
+    /// Returns `true` if the instruction belongs to code generated by the compiler
+    pub fn is_generated(&self) -> bool {
+        self.source.generated
+    }
+}
+
+/// Maps the program counters of a bytecode to instructions and their source locations.
+///
+/// The source map of solc contains one element per instruction, so program counters are first
+/// mapped to the index of their instruction, skipping the data of `PUSH` instructions. The source
+/// index of the element is then resolved to a file via the id of the compiled source files, see
+/// [`VersionedSourceFiles::into_ids()`](crate::sources::VersionedSourceFiles::into_ids).
+///
+/// The compiler also generates Yul helper code that's referenced by source indices following the
+/// ids of the source files. These sources are available in the `generatedSources` output of the
+/// bytecode and can be added with [`Self::with_generated_sources()`], otherwise their instructions
+/// can't be resolved.
+///
+/// # Examples
+///
+/// ```no_run
+/// use foundry_compilers::{debug::DebugInfo, CompilerConfig, Project, Solc};
+///
+/// let project = Project::builder().build(CompilerConfig::Specific(Solc::new("solc")?))?;
+/// let output = project.compile()?;
+/// let artifact = output.find_first("Greeter").unwrap();
+/// let files = output.output().sources.clone().into_ids();
+/// let debug_info = DebugInfo::creation(artifact.bytecode.as_ref().unwrap(), files)?
+///     .with_generated_sources(&artifact.generated_sources);
+/// if let Some(location) = debug_info.resolve(0) {
+///     println!("{}:{}:{}", location.path().display(), location.line, location.column);
+/// }
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct DebugInfo {
+    /// The program counter of every instruction
+    pcs: Vec<usize>,
+    source_map: SourceMap,
+    sources: BTreeMap<u32, DebugSource>,
+}
+
+impl DebugInfo {
+    /// Creates the debug info of the bytecode with its source map and the files referenced by the
+    /// source map, by source index.
+    pub fn new(
+        code: &[u8],
+        source_map: SourceMap,
+        sources: impl IntoIterator<Item = (u32, DebugSource)>,
+    ) -> Self {
+        Self { pcs: instruction_pcs(code), source_map, sources: sources.into_iter().collect() }
+    }
+
+    /// Creates the debug info of creation bytecode.
+    ///
+    /// `files` maps source indices to paths, the files that are referenced by the source map are
+    /// read from disk. Unlinked bytecode is supported, since linking doesn't move instructions.
+    pub fn creation(
+        bytecode: &CompactBytecode,
+        files: impl IntoIterator<Item = (u32, PathBuf)>,
+    ) -> Result<Self> {
+        Self::from_parts(&bytecode.object, bytecode.source_map(), files)
+    }
+
+    /// Creates the debug info of deployed bytecode, see [`Self::creation()`].
+    pub fn deployed(
+        bytecode: &CompactDeployedBytecode,
+        files: impl IntoIterator<Item = (u32, PathBuf)>,
+    ) -> Result<Self> {
+        let bytecode = bytecode
+            .bytecode
+            .as_ref()
+            .ok_or_else(|| SolcError::msg("missing deployed bytecode"))?;
+        Self::creation(bytecode, files)
+    }
+
+    fn from_parts(
+        object: &BytecodeObject,
+        source_map: Option<std::result::Result<SourceMap, SyntaxError>>,
+        files: impl IntoIterator<Item = (u32, PathBuf)>,
+    ) -> Result<Self> {
+        let code = object
+            .bytes_with_zeroed_placeholders()
+            .ok_or_else(|| SolcError::msg("invalid bytecode object"))?;
+        let source_map = source_map
+            .ok_or_else(|| SolcError::msg("missing source map"))?
+            .map_err(|err| SolcError::msg(format!("invalid source map: {err}")))?;
+
+        let ids = source_map.iter().filter_map(|element| element.index).collect::<BTreeSet<_>>();
+        let mut sources = Vec::new();
+        for (id, path) in files {
+            if ids.contains(&id) {
+                let source = Source::read(&path)?;
+                sources.push((id, DebugSource::new(path, source.content.as_str())));
+            }
+        }
+
+        Ok(Self::new(&code, source_map, sources))
+    }
+
+    /// Adds the sources generated by the compiler as virtual files, see [`DebugSource::generated()`].
+    ///
+    /// Files of the project take precedence over generated sources with the same index.
+    #[must_use]
+    pub fn with_generated_sources<'a>(
+        mut self,
+        sources: impl IntoIterator<Item = &'a GeneratedSource>,
+    ) -> Self {
+        for source in sources {
+            self.sources.entry(source.id).or_insert_with(|| DebugSource::generated(source));
+        }
+        self
+    }
+
+    /// Returns the index of the instruction at the program counter.
+    ///
+    /// Returns `None` if the program counter points into the data of a `PUSH` instruction or past
+    /// the end of the bytecode.
+    pub fn instruction(&self, pc: usize) -> Option<usize> {
+        self.pcs.binary_search(&pc).ok()
+    }
+
+    /// Returns the program counter of the instruction with the given index
+    pub fn pc(&self, instruction: usize) -> Option<usize> {
+        self.pcs.get(instruction).copied()
+    }
+
+    /// Returns the source map element of the instruction at the program counter
+    pub fn source_element(&self, pc: usize) -> Option<&SourceElement> {
+        self.source_map.get(self.instruction(pc)?)
+    }
+
+    /// Resolves the program counter to the file, byte range, line and column of its instruction.
+    ///
+    /// Returns `None` if the program counter doesn't point to an instruction, or if the
+    /// instruction isn't associated with any of the sources, which is the case for some
+    /// compiler-generated code and for generated sources that weren't added.
+    pub fn resolve(&self, pc: usize) -> Option<ResolvedLocation<'_>> {
+        let instruction = self.instruction(pc)?;
+        let element = self.source_map.get(instruction)?;
+        let source = self.sources.get(&element.index?)?;
+        let (line, column) = source.line_column(element.offset);
+        Some(ResolvedLocation {
+            pc,
+            instruction,
+            element,
+            source,
+            range: element.offset..element.offset + element.length,
+            line,
+            column,
+        })
+    }
+
+    /// Returns the source map
+    pub fn source_map(&self) -> &SourceMap {
+        &self.source_map
+    }
+
+    /// Returns the sources referenced by the source map, by source index
+    pub fn sources(&self) -> &BTreeMap<u32, DebugSource> {
+        &self.sources
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_resolve_pcs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let a = tmp.path().join("A.sol");
+        let b = tmp.path().join("B.sol");
+        std::fs::write(&a, "contract A {\n    uint x;\n}\n").unwrap();
+        std::fs::write(&b, "library B {}\n").unwrap();
+
+        // PUSH1 0x80, PUSH20 <placeholder>, MSTORE, PUSH2 0x0102, STOP
+        let bytecode: CompactBytecode = serde_json::from_value(serde_json::json!({
+            "object": "608073__$fb58009a6b1ecea3b9d99bedd645df4ec3$__5261010200",
+            "sourceMap": "0:27:0:-:0;17:6:0;;-1:-1:-1;0:12:1",
+            "linkReferences": {}
+        }))
+        .unwrap();
+        let debug_info =
+            DebugInfo::creation(&bytecode, [(0, a.clone()), (1, b.clone()), (2, "C.sol".into())])
+                .unwrap();
+
+        assert_eq!(debug_info.sources().keys().copied().collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!((0..28).filter_map(|pc| debug_info.instruction(pc)).count(), 5);
+        assert_eq!(debug_info.instruction(24), Some(3));
+        assert_eq!(debug_info.pc(4), Some(27));
+
+        let location = debug_info.resolve(0).unwrap();
+        assert_eq!((location.path(), location.line, location.column), (a.as_path(), 1, 1));
+
+        // inherits the location of the previous element
+        let location = debug_info.resolve(23).unwrap();
+        assert_eq!(location.instruction, 2);
+        assert_eq!(location.range, 17..23);
+        assert_eq!((location.line, location.column), (2, 5));
+        assert_eq!(location.snippet(), "uint x");
+
+        // compiler-generated code
+        assert!(debug_info.source_element(24).unwrap().index.is_none());
+        assert_eq!(debug_info.resolve(24), None);
+
+        let location = debug_info.resolve(27).unwrap();
+        assert_eq!((location.path(), location.snippet()), (b.as_path(), "library B {}"));
+
+        // push data
+        assert_eq!(debug_info.resolve(1), None);
+        assert_eq!(debug_info.resolve(28), None);
+    }
+
+    #[test]
+    fn can_resolve_generated_sources() {
+        // PUSH1 0x01, PUSH1 0x02, STOP
+        let code = [0x60, 0x01, 0x60, 0x02, 0x00];
+        let source_map = crate::sourcemap::parse("0:12:0:-:0;6:15:1;0:12:0").unwrap();
+        let generated: GeneratedSource = serde_json::from_value(serde_json::json!({
+            "ast": {},
+            "contents": "{\n    function f() {}\n}\n",
+            "id": 1,
+            "language": "Yul",
+            "name": "#utility.yul"
+        }))
+        .unwrap();
+
+        let debug_info =
+            DebugInfo::new(&code, source_map, [(0, DebugSource::new("B.sol", "library B {}\n"))]);
+        assert_eq!(debug_info.resolve(2), None);
+
+        let debug_info = debug_info.with_generated_sources([&generated]);
+        let location = debug_info.resolve(2).unwrap();
+        assert!(location.is_generated());
+        assert_eq!(location.path(), Path::new("#utility.yul"));
+        assert_eq!((location.line, location.column), (2, 5));
+        assert_eq!(location.snippet(), "function f() {}");
+
+        assert!(!debug_info.resolve(4).unwrap().is_generated());
+    }
+}
@@ -0,0 +1,390 @@
+//! Disassembly of EVM bytecode.
+
+use crate::{
+    artifacts::{BytecodeObject, CompactBytecode, CompactDeployedBytecode},
+    cbor::CborMetadata,
+    debug::{push_size, DebugInfo},
+    error::{Result, SolcError},
+    sourcemap::SourceElement,
+};
+use alloy_primitives::hex;
+use std::{collections::BTreeMap, fmt, ops::Range};
+
+/// Returns the mnemonic of the opcode, or `None` if the opcode is undefined.
+pub fn opcode_name(opcode: u8) -> Option<&'static str> {
+    let name = match opcode {
+        0x00 => "STOP",
+        0x01 => "ADD",
+        0x02 => "MUL",
+        0x03 => "SUB",
+        0x04 => "DIV",
+        0x05 => "SDIV",
+        0x06 => "MOD",
+        0x07 => "SMOD",
+        0x08 => "ADDMOD",
+        0x09 => "MULMOD",
+        0x0a => "EXP",
+        0x0b => "SIGNEXTEND",
+        0x10 => "LT",
+        0x11 => "GT",
+        0x12 => "SLT",
+        0x13 => "SGT",
+        0x14 => "EQ",
+        0x15 => "ISZERO",
+        0x16 => "AND",
+        0x17 => "OR",
+        0x18 => "XOR",
+        0x19 => "NOT",
+        0x1a => "BYTE",
+        0x1b => "SHL",
+        0x1c => "SHR",
+        0x1d => "SAR",
+        0x20 => "KECCAK256",
+        0x30 => "ADDRESS",
+        0x31 => "BALANCE",
+        0x32 => "ORIGIN",
+        0x33 => "CALLER",
+        0x34 => "CALLVALUE",
+        0x35 => "CALLDATALOAD",
+        0x36 => "CALLDATASIZE",
+        0x37 => "CALLDATACOPY",
+        0x38 => "CODESIZE",
+        0x39 => "CODECOPY",
+        0x3a => "GASPRICE",
+        0x3b => "EXTCODESIZE",
+        0x3c => "EXTCODECOPY",
+        0x3d => "RETURNDATASIZE",
+        0x3e => "RETURNDATACOPY",
+        0x3f => "EXTCODEHASH",
+        0x40 => "BLOCKHASH",
+        0x41 => "COINBASE",
+        0x42 => "TIMESTAMP",
+        0x43 => "NUMBER",
+        0x44 => "PREVRANDAO",
+        0x45 => "GASLIMIT",
+        0x46 => "CHAINID",
+        0x47 => "SELFBALANCE",
+        0x48 => "BASEFEE",
+        0x49 => "BLOBHASH",
+        0x4a => "BLOBBASEFEE",
+        0x50 => "POP",
+        0x51 => "MLOAD",
+        0x52 => "MSTORE",
+        0x53 => "MSTORE8",
+        0x54 => "SLOAD",
+        0x55 => "SSTORE",
+        0x56 => "JUMP",
+        0x57 => "JUMPI",
+        0x58 => "PC",
+        0x59 => "MSIZE",
+        0x5a => "GAS",
+        0x5b => "JUMPDEST",
+        0x5c => "TLOAD",
+        0x5d => "TSTORE",
+        0x5e => "MCOPY",
+        0x5f => "PUSH0",
+        0x60 => "PUSH1",
+        0x61 => "PUSH2",
+        0x62 => "PUSH3",
+        0x63 => "PUSH4",
+        0x64 => "PUSH5",
+        0x65 => "PUSH6",
+        0x66 => "PUSH7",
+        0x67 => "PUSH8",
+        0x68 => "PUSH9",
+        0x69 => "PUSH10",
+        0x6a => "PUSH11",
+        0x6b => "PUSH12",
+        0x6c => "PUSH13",
+        0x6d => "PUSH14",
+        0x6e => "PUSH15",
+        0x6f => "PUSH16",
+        0x70 => "PUSH17",
+        0x71 => "PUSH18",
+        0x72 => "PUSH19",
+        0x73 => "PUSH20",
+        0x74 => "PUSH21",
+        0x75 => "PUSH22",
+        0x76 => "PUSH23",
+        0x77 => "PUSH24",
+        0x78 => "PUSH25",
+        0x79 => "PUSH26",
+        0x7a => "PUSH27",
+        0x7b => "PUSH28",
+        0x7c => "PUSH29",
+        0x7d => "PUSH30",
+        0x7e => "PUSH31",
+        0x7f => "PUSH32",
+        0x80 => "DUP1",
+        0x81 => "DUP2",
+        0x82 => "DUP3",
+        0x83 => "DUP4",
+        0x84 => "DUP5",
+        0x85 => "DUP6",
+        0x86 => "DUP7",
+        0x87 => "DUP8",
+        0x88 => "DUP9",
+        0x89 => "DUP10",
+        0x8a => "DUP11",
+        0x8b => "DUP12",
+        0x8c => "DUP13",
+        0x8d => "DUP14",
+        0x8e => "DUP15",
+        0x8f => "DUP16",
+        0x90 => "SWAP1",
+        0x91 => "SWAP2",
+        0x92 => "SWAP3",
+        0x93 => "SWAP4",
+        0x94 => "SWAP5",
+        0x95 => "SWAP6",
+        0x96 => "SWAP7",
+        0x97 => "SWAP8",
+        0x98 => "SWAP9",
+        0x99 => "SWAP10",
+        0x9a => "SWAP11",
+        0x9b => "SWAP12",
+        0x9c => "SWAP13",
+        0x9d => "SWAP14",
+        0x9e => "SWAP15",
+        0x9f => "SWAP16",
+        0xa0 => "LOG0",
+        0xa1 => "LOG1",
+        0xa2 => "LOG2",
+        0xa3 => "LOG3",
+        0xa4 => "LOG4",
+        0xf0 => "CREATE",
+        0xf1 => "CALL",
+        0xf2 => "CALLCODE",
+        0xf3 => "RETURN",
+        0xf4 => "DELEGATECALL",
+        0xf5 => "CREATE2",
+        0xfa => "STATICCALL",
+        0xfd => "REVERT",
+        0xfe => "INVALID",
+        0xff => "SELFDESTRUCT",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// A disassembled instruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    /// The program counter of the instruction
+    pub pc: usize,
+    /// The opcode of the instruction
+    pub opcode: u8,
+    /// The data of a `PUSH` instruction, which is shorter than expected if the code ends early
+    pub data: Vec<u8>,
+    /// The library whose address is pushed, if the bytecode is unlinked.
+    ///
+    /// This is the fully qualified name of the library if the link references are known,
+    /// otherwise the placeholder.
+    pub library: Option<String>,
+    /// The source map element of the instruction, see [`Disassembly::annotate()`]
+    pub source_element: Option<SourceElement>,
+    /// The source code of the instruction, see [`Disassembly::annotate()`]
+    pub snippet: Option<String>,
+}
+
+impl Instruction {
+    /// Returns the mnemonic of the opcode, or `None` if the opcode is undefined
+    pub fn name(&self) -> Option<&'static str> {
+        opcode_name(self.opcode)
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#06x}: ", self.pc)?;
+        match self.name() {
+            Some(name) => f.write_str(name)?,
+            None => write!(f, "UNKNOWN({:#04x})", self.opcode)?,
+        }
+        if let Some(library) = &self.library {
+            write!(f, " <{library}>")?;
+        } else if !self.data.is_empty() {
+            write!(f, " {}", hex::encode_prefixed(&self.data))?;
+        }
+        if let Some(snippet) = &self.snippet {
+            let line = snippet.lines().next().unwrap_or_default().trim();
+            if !line.is_empty() {
+                write!(f, "\t// {line}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The disassembled instructions of a bytecode.
+///
+/// # Examples
+///
+/// ```
+/// use foundry_compilers::disasm::Disassembly;
+///
+/// let disassembly = Disassembly::new(&[0x60, 0x80, 0x60, 0x40, 0x52]);
+/// assert_eq!(disassembly.to_string(), "0x0000: PUSH1 0x80\n0x0002: PUSH1 0x40\n0x0004: MSTORE\n");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Disassembly {
+    /// The instructions, in order
+    pub instructions: Vec<Instruction>,
+    /// The range of the CBOR encoded metadata that the compiler appends to the code, which isn't
+    /// disassembled
+    pub metadata: Option<Range<usize>>,
+}
+
+impl Disassembly {
+    /// Disassembles the code, stopping at the metadata trailer if there is one.
+    pub fn new(code: &[u8]) -> Self {
+        let metadata =
+            CborMetadata::decode(code).map(|metadata| code.len() - metadata.len..code.len());
+        let end = metadata.as_ref().map_or(code.len(), |metadata| metadata.start);
+
+        let mut instructions = Vec::new();
+        let mut pc = 0;
+        while pc < end {
+            let opcode = code[pc];
+            let data_end = (pc + 1 + push_size(opcode)).min(code.len());
+            instructions.push(Instruction {
+                pc,
+                opcode,
+                data: code[pc + 1..data_end].to_vec(),
+                library: None,
+                source_element: None,
+                snippet: None,
+            });
+            pc = data_end;
+        }
+
+        Self { instructions, metadata }
+    }
+
+    /// Disassembles the bytecode object, which may be unlinked.
+    ///
+    /// Instructions that push the address of an unlinked library are marked with the placeholder
+    /// of the library.
+    pub fn from_object(object: &BytecodeObject) -> Result<Self> {
+        Self::with_libraries(object, |_, placeholder| Some(placeholder.to_string()))
+    }
+
+    /// Disassembles the bytecode, marking unlinked libraries with their fully qualified name.
+    pub fn from_bytecode(bytecode: &CompactBytecode) -> Result<Self> {
+        let mut libraries = BTreeMap::new();
+        for (file, libs) in &bytecode.link_references {
+            for (library, offsets) in libs {
+                for offset in offsets {
+                    libraries.insert(offset.start as usize, format!("{file}:{library}"));
+                }
+            }
+        }
+        Self::with_libraries(&bytecode.object, |offset, placeholder| {
+            Some(libraries.get(&offset).cloned().unwrap_or_else(|| placeholder.to_string()))
+        })
+    }
+
+    /// Disassembles the deployed bytecode, see [`Self::from_bytecode()`].
+    pub fn from_deployed(bytecode: &CompactDeployedBytecode) -> Result<Self> {
+        let bytecode = bytecode
+            .bytecode
+            .as_ref()
+            .ok_or_else(|| SolcError::msg("missing deployed bytecode"))?;
+        Self::from_bytecode(bytecode)
+    }
+
+    /// Disassembles the object and names the library of every placeholder, by its byte offset.
+    fn with_libraries(
+        object: &BytecodeObject,
+        mut library: impl FnMut(usize, &str) -> Option<String>,
+    ) -> Result<Self> {
+        let code = object
+            .bytes_with_zeroed_placeholders()
+            .ok_or_else(|| SolcError::msg("invalid bytecode object"))?;
+        let mut disassembly = Self::new(&code);
+
+        if let Some(unlinked) = object.as_str() {
+            let unlinked = unlinked.strip_prefix("0x").unwrap_or(unlinked);
+            let mut placeholders = BTreeMap::new();
+            let mut pos = 0;
+            while let Some(start) = unlinked[pos..].find("__").map(|idx| pos + idx) {
+                let end = (start + 40).min(unlinked.len());
+                placeholders.insert(start / 2, &unlinked[start..end]);
+                pos = end;
+            }
+
+            for instruction in &mut disassembly.instructions {
+                let data = instruction.pc + 1..instruction.pc + 1 + instruction.data.len();
+                if let Some((offset, placeholder)) = placeholders.range(data).next() {
+                    instruction.library = library(*offset, placeholder);
+                }
+            }
+        }
+
+        Ok(disassembly)
+    }
+
+    /// Attaches the source map element and the source code of every instruction.
+    pub fn annotate(&mut self, debug_info: &DebugInfo) {
+        for instruction in &mut self.instructions {
+            instruction.source_element = debug_info.source_element(instruction.pc).cloned();
+            instruction.snippet =
+                debug_info.resolve(instruction.pc).map(|location| location.snippet().to_string());
+        }
+    }
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for instruction in &self.instructions {
+            writeln!(f, "{instruction}")?;
+        }
+        if let Some(metadata) = &self.metadata {
+            writeln!(f, "{:#06x}: <metadata, {} bytes>", metadata.start, metadata.len())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debug::DebugSource;
+
+    #[test]
+    fn can_disassemble() {
+        // PUSH1 0x80, PUSH20 <Lib>, PUSH0, STOP, metadata `{"solc": 0.8.19}`
+        let bytecode: CompactBytecode = serde_json::from_value(serde_json::json!({
+            "object": "608073__$fb58009a6b1ecea3b9d99bedd645df4ec3$__5f00a164736f6c6343000813000a",
+            "sourceMap": "0:23:0:-:0;;9:3:0;;",
+            "linkReferences": { "src/Lib.sol": { "Lib": [{ "start": 3, "length": 20 }] } }
+        }))
+        .unwrap();
+
+        let disassembly = Disassembly::from_object(&bytecode.object).unwrap();
+        assert_eq!(
+            disassembly.instructions[1].library.as_deref(),
+            Some("__$fb58009a6b1ecea3b9d99bedd645df4ec3$__")
+        );
+
+        let mut disassembly = Disassembly::from_bytecode(&bytecode).unwrap();
+        assert_eq!(disassembly.instructions.len(), 4);
+        assert_eq!(disassembly.metadata, Some(25..37));
+
+        let code = bytecode.object.bytes_with_zeroed_placeholders().unwrap();
+        let source_map = bytecode.source_map().unwrap().unwrap();
+        let source = DebugSource::new("src/A.sol", "contract A { Lib.f(); }\n");
+        disassembly.annotate(&DebugInfo::new(&code, source_map, [(0, source)]));
+        assert_eq!(disassembly.instructions[2].source_element.as_ref().unwrap().offset, 9);
+
+        assert_eq!(
+            disassembly.to_string(),
+            "0x0000: PUSH1 0x80\t// contract A { Lib.f(); }
+0x0002: PUSH20 <src/Lib.sol:Lib>\t// contract A { Lib.f(); }
+0x0017: PUSH0\t// A {
+0x0018: STOP\t// A {
+0x0019: <metadata, 12 bytes>
+"
+        );
+    }
+}
@@ -69,6 +69,10 @@ pub enum SolcError {
     #[error("no artifact found for `{}:{}`", .0.display(), .1)]
     ArtifactNotFound(PathBuf, String),
 
+    /// The cache file was written in a format that can't be migrated, e.g. by a newer version.
+    #[error("unknown cache format \"{format}\" in \"{}\"; delete the cache file to rebuild it", .path.display())]
+    UnknownCacheFormat { format: String, path: PathBuf },
+
     #[cfg(feature = "project-util")]
     #[error(transparent)]
     FsExtra(#[from] fs_extra::error::Error),
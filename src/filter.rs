@@ -1,15 +1,23 @@
 //! Types to apply filter to input types
 
 use crate::{
-    artifacts::output_selection::OutputSelection,
-    compilers::CompilerSettings,
-    resolver::{parse::SolData, GraphEdges},
+    artifacts::output_selection::{FileOutputSelection, OutputSelection},
+    compilers::{CompilerSettings, ParsedSource},
+    info::ContractInfo,
+    resolver::{
+        parse::{SolContract, SolContractKind, SolData},
+        GraphEdges,
+    },
     Source, Sources,
 };
+use globset::{GlobBuilder, GlobMatcher};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::{btree_map::Entry, BTreeMap, BTreeSet, HashSet, VecDeque},
     fmt::{self, Formatter},
-    path::{Path, PathBuf},
+    fs, ops,
+    path::{Component, Path, PathBuf},
 };
 
 /// A predicate property that determines whether a file satisfies a certain condition
@@ -48,6 +56,259 @@ impl FileFilter for TestFileFilter {
     }
 }
 
+/// A serializable [FileFilter] built from glob patterns, directories and regexes, which can be
+/// combined with [`PathFilter::and()`], [`PathFilter::or()`] and `!`.
+///
+/// Relative glob patterns and directories are relative to the project root: they only match
+/// relative paths, so a filter that's applied to the absolute paths of a project's sources must be
+/// scoped to the project with [`PathFilter::relative_to()`]. Absolute patterns and directories
+/// always match against the absolute path.
+///
+/// Filters are (de)serialized as data, e.g. in JSON:
+///
+/// ```json
+/// { "and": [{ "dir": "test" }, { "not": { "glob": "**/fork/**" } }] }
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use foundry_compilers::{FileFilter, PathFilter};
+/// use std::path::Path;
+///
+/// let filter = PathFilter::dir("test").and(!PathFilter::glob("**/fork/**")?)
+///     .or(PathFilter::contract_name("^Counter$")?)
+///     .relative_to("/project");
+/// assert!(filter.is_match(Path::new("/project/test/Counter.t.sol")));
+/// assert!(!filter.is_match(Path::new("/project/test/fork/Fork.t.sol")));
+/// assert!(filter.is_match(Path::new("/project/src/Counter.sol")));
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PathFilter {
+    /// Matches files whose path matches the glob pattern
+    Glob(GlobPattern),
+    /// Matches files located in the directory or any of its subdirectories
+    Dir(PathBuf),
+    /// Matches files whose name without extensions, like `Counter` for `Counter.t.sol`, matches
+    /// the regex
+    ContractName(RegexPattern),
+    /// Matches files that are matched by all filters
+    And(Vec<PathFilter>),
+    /// Matches files that are matched by any filter
+    Or(Vec<PathFilter>),
+    /// Matches files that are not matched by the filter
+    Not(Box<PathFilter>),
+}
+
+impl PathFilter {
+    /// Creates a filter that includes files matching the glob pattern
+    pub fn glob(pattern: impl Into<String>) -> Result<Self, globset::Error> {
+        Ok(Self::Glob(GlobPattern::new(pattern)?))
+    }
+
+    /// Creates a filter that excludes files matching the glob pattern
+    pub fn exclude(pattern: impl Into<String>) -> Result<Self, globset::Error> {
+        Ok(!Self::glob(pattern)?)
+    }
+
+    /// Creates a filter that includes all files in the directory
+    pub fn dir(dir: impl Into<PathBuf>) -> Self {
+        Self::Dir(dir.into())
+    }
+
+    /// Creates a filter that includes files whose name without extensions matches the regex
+    pub fn contract_name(regex: impl Into<String>) -> Result<Self, regex::Error> {
+        Ok(Self::ContractName(RegexPattern::new(regex)?))
+    }
+
+    /// Returns a filter that matches files that are matched by this and the `other` filter
+    #[must_use]
+    pub fn and(self, other: Self) -> Self {
+        match self {
+            Self::And(mut filters) => {
+                filters.push(other);
+                Self::And(filters)
+            }
+            filter => Self::And(vec![filter, other]),
+        }
+    }
+
+    /// Returns a filter that matches files that are matched by this or the `other` filter
+    #[must_use]
+    pub fn or(self, other: Self) -> Self {
+        match self {
+            Self::Or(mut filters) => {
+                filters.push(other);
+                Self::Or(filters)
+            }
+            filter => Self::Or(vec![filter, other]),
+        }
+    }
+
+    /// Resolves the relative globs and directories of the filter against the `root`, like the
+    /// project root.
+    pub fn relative_to(self, root: impl Into<PathBuf>) -> RootedPathFilter {
+        RootedPathFilter { root: root.into(), filter: self }
+    }
+
+    /// Returns `true` if the file is matched, where `relative` is the path of the file relative to
+    /// the root, if it's inside of it, which is used by relative globs and directories.
+    fn matches(&self, file: &Path, relative: Option<&Path>) -> bool {
+        match self {
+            Self::Glob(glob) if glob.is_absolute() => glob.is_match(file),
+            Self::Glob(glob) => relative.map_or(false, |file| glob.is_match(file)),
+            Self::Dir(dir) if dir.is_absolute() => file.starts_with(dir),
+            Self::Dir(dir) => {
+                let dir = dir.components().filter(|c| *c != Component::CurDir).collect::<PathBuf>();
+                relative.map_or(false, |file| file.starts_with(dir))
+            }
+            Self::ContractName(regex) => file
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.split('.').next())
+                .map_or(false, |name| regex.0.is_match(name)),
+            Self::And(filters) => filters.iter().all(|filter| filter.matches(file, relative)),
+            Self::Or(filters) => filters.iter().any(|filter| filter.matches(file, relative)),
+            Self::Not(filter) => !filter.matches(file, relative),
+        }
+    }
+}
+
+impl ops::Not for PathFilter {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        match self {
+            Self::Not(filter) => *filter,
+            filter => Self::Not(Box::new(filter)),
+        }
+    }
+}
+
+impl FileFilter for PathFilter {
+    fn is_match(&self, file: &Path) -> bool {
+        self.matches(file, file.is_relative().then_some(file))
+    }
+}
+
+/// A [PathFilter] whose relative globs and directories are resolved against a root, see
+/// [`PathFilter::relative_to()`].
+///
+/// Files outside of the root are only matched by absolute globs and directories.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RootedPathFilter {
+    pub root: PathBuf,
+    pub filter: PathFilter,
+}
+
+impl FileFilter for RootedPathFilter {
+    fn is_match(&self, file: &Path) -> bool {
+        let relative =
+            if file.is_relative() { Some(file) } else { file.strip_prefix(&self.root).ok() };
+        self.filter.matches(file, relative)
+    }
+}
+
+/// A glob pattern of a [PathFilter], (de)serialized as the pattern string
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct GlobPattern {
+    pattern: String,
+    matcher: GlobMatcher,
+}
+
+impl GlobPattern {
+    /// Compiles the glob pattern
+    pub fn new(pattern: impl Into<String>) -> Result<Self, globset::Error> {
+        let pattern = pattern.into();
+        // `*` doesn't match path separators, like in shells
+        let matcher = GlobBuilder::new(pattern.trim_start_matches("./"))
+            .literal_separator(true)
+            .build()?
+            .compile_matcher();
+        Ok(Self { pattern, matcher })
+    }
+
+    /// Returns the pattern as it was declared
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns `true` if the pattern is an absolute path, otherwise it's relative to the root the
+    /// filter is applied to
+    pub fn is_absolute(&self) -> bool {
+        Path::new(&self.pattern).is_absolute()
+    }
+
+    /// Returns `true` if the path matches the pattern
+    pub fn is_match(&self, path: &Path) -> bool {
+        self.matcher.is_match(path)
+    }
+}
+
+impl PartialEq for GlobPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+    }
+}
+
+impl Eq for GlobPattern {}
+
+impl TryFrom<String> for GlobPattern {
+    type Error = globset::Error;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        Self::new(pattern)
+    }
+}
+
+impl From<GlobPattern> for String {
+    fn from(glob: GlobPattern) -> Self {
+        glob.pattern
+    }
+}
+
+/// A regex of a [PathFilter], (de)serialized as the regex string
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RegexPattern(Regex);
+
+impl RegexPattern {
+    /// Compiles the regex
+    pub fn new(regex: impl Into<String>) -> Result<Self, regex::Error> {
+        Ok(Self(Regex::new(&regex.into())?))
+    }
+
+    /// Returns the regex
+    pub fn as_regex(&self) -> &Regex {
+        &self.0
+    }
+}
+
+impl PartialEq for RegexPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Eq for RegexPattern {}
+
+impl TryFrom<String> for RegexPattern {
+    type Error = regex::Error;
+
+    fn try_from(regex: String) -> Result<Self, Self::Error> {
+        Self::new(regex)
+    }
+}
+
+impl From<RegexPattern> for String {
+    fn from(regex: RegexPattern) -> Self {
+        regex.0.as_str().to_string()
+    }
+}
+
 /// Wrapper around a [FileFilter] that includes files matched by the inner filter and their link
 /// references obtained from [GraphEdges].
 pub struct SolcSparseFileFilter<T> {
@@ -85,6 +346,150 @@ impl<T: FileFilter> SparseOutputFileFilter<SolData> for SolcSparseFileFilter<T>
     }
 }
 
+/// A [SparseOutputFileFilter] that selects the output of specific contracts only.
+///
+/// This matches the files that define the given contracts, so [`crate::Project::compile_sparse()`]
+/// only compiles these files and their imports. The output selection is narrowed to the given
+/// contracts, the contracts they or their base contracts deploy via `new`, and the libraries they
+/// may need to link, instead of all contracts of the compiled files.
+///
+/// Contracts without a path are matched by name in all files.
+///
+/// # Examples
+///
+/// ```no_run
+/// use foundry_compilers::{info::ContractInfo, CompilerConfig, ContractFilter, Project, Solc};
+///
+/// let project = Project::builder().build(CompilerConfig::Specific(Solc::new("solc")?))?;
+/// let filter = ContractFilter::new([ContractInfo::new("test/Counter.t.sol:CounterTest")]);
+/// let output = project.compile_sparse(Box::new(filter))?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ContractFilter {
+    contracts: Vec<ContractInfo>,
+}
+
+impl ContractFilter {
+    /// Creates a filter for the given contracts
+    pub fn new(contracts: impl IntoIterator<Item = impl Into<ContractInfo>>) -> Self {
+        Self { contracts: contracts.into_iter().map(Into::into).collect() }
+    }
+
+    /// Returns the contracts of the filter
+    pub fn contracts(&self) -> &[ContractInfo] {
+        &self.contracts
+    }
+
+    /// Returns `true` if the contract named `name` of the `file` is selected by the filter
+    fn is_selected(&self, file: &Path, name: &str) -> bool {
+        self.contracts.iter().any(|info| {
+            info.name == name && info.path.as_ref().map_or(true, |path| file.ends_with(path))
+        })
+    }
+
+    /// Returns the contracts that require output if the given `file` is compiled, mapped to the
+    /// files they are defined in.
+    fn select(
+        &self,
+        file: &Path,
+        graph: &GraphEdges<SolData>,
+    ) -> BTreeMap<PathBuf, BTreeSet<String>> {
+        let mut selected = BTreeMap::<PathBuf, BTreeSet<String>>::new();
+        let Some(data) = graph.get_parsed_source(file) else { return selected };
+        let mut queue = data
+            .contracts
+            .iter()
+            .filter(|contract| self.is_selected(file, &contract.name))
+            .map(|contract| (file.to_path_buf(), contract))
+            .collect::<VecDeque<_>>();
+        if queue.is_empty() {
+            return selected;
+        }
+
+        while let Some((path, contract)) = queue.pop_front() {
+            if !selected.entry(path.clone()).or_default().insert(contract.name.clone()) {
+                continue;
+            }
+            // contracts deployed by base contracts are deployed by the contract as well
+            let mut visited = HashSet::new();
+            let mut lineage = vec![(path, contract)];
+            while let Some((path, contract)) = lineage.pop() {
+                if !visited.insert((path.clone(), &contract.name)) {
+                    continue;
+                }
+                queue.extend(
+                    contract.deploys.iter().filter_map(|name| find_contract(graph, &path, name)),
+                );
+                lineage.extend(
+                    contract.bases.iter().filter_map(|name| find_contract(graph, &path, name)),
+                );
+            }
+        }
+
+        // libraries with external functions need to be deployed and linked
+        for file in graph.imports(file).into_iter().chain(Some(&file.to_path_buf())) {
+            let Some(data) = graph.get_parsed_source(file) else { continue };
+            let libraries = data.contracts.iter().filter(|c| c.kind == SolContractKind::Library);
+            for (contract, library) in libraries.zip(&data.libraries) {
+                if !library.is_inlined() {
+                    selected.entry(file.clone()).or_default().insert(contract.name.clone());
+                }
+            }
+        }
+
+        selected
+    }
+}
+
+/// Returns the contract named `name` that's visible in `file`, either because it's defined in the
+/// file or in one of its imports.
+fn find_contract<'a>(
+    graph: &'a GraphEdges<SolData>,
+    file: &Path,
+    name: &str,
+) -> Option<(PathBuf, &'a SolContract)> {
+    let find = |file: &Path| {
+        graph.get_parsed_source(file)?.contracts.iter().find(|contract| contract.name == name)
+    };
+    if let Some(contract) = find(file) {
+        return Some((file.to_path_buf(), contract));
+    }
+    let mut imports = graph.imports(file).into_iter().collect::<Vec<_>>();
+    imports.sort();
+    imports.into_iter().find_map(|import| Some((import.clone(), find(import)?)))
+}
+
+impl FileFilter for ContractFilter {
+    fn is_match(&self, file: &Path) -> bool {
+        let mut defined = None;
+        self.contracts.iter().any(|info| match &info.path {
+            Some(path) => file.ends_with(path),
+            None => defined
+                .get_or_insert_with(|| {
+                    fs::read_to_string(file)
+                        .map(|content| SolData::parse(&content, file).contract_names())
+                        .unwrap_or_default()
+                })
+                .contains(&info.name),
+        })
+    }
+}
+
+impl SparseOutputFileFilter<SolData> for ContractFilter {
+    fn sparse_sources(&self, file: &Path, graph: &GraphEdges<SolData>) -> Vec<PathBuf> {
+        self.select(file, graph).into_keys().collect()
+    }
+
+    fn sparse_contracts(
+        &self,
+        file: &Path,
+        graph: &GraphEdges<SolData>,
+    ) -> BTreeMap<PathBuf, BTreeSet<String>> {
+        self.select(file, graph)
+    }
+}
+
 /// This trait behaves in a similar way to [FileFilter] but used to configure [OutputSelection]
 /// configuration. In certain cases, we might want to include some of the file dependencies into the
 /// compiler output even if we might not be directly interested in them.
@@ -98,6 +503,20 @@ pub trait SparseOutputFileFilter<D>: FileFilter {
     ///
     /// Might return an empty list if no files should be compiled.
     fn sparse_sources(&self, file: &Path, graph: &GraphEdges<D>) -> Vec<PathBuf>;
+
+    /// Receives path to the file and resolved project sources graph.
+    ///
+    /// Returns the names of the contracts that should be compiled with full output selection,
+    /// mapped to the files returned by [Self::sparse_sources] that define them. Files that are not
+    /// part of the map are compiled with full output selection for all their contracts, which is
+    /// the default.
+    fn sparse_contracts(
+        &self,
+        _file: &Path,
+        _graph: &GraphEdges<D>,
+    ) -> BTreeMap<PathBuf, BTreeSet<String>> {
+        BTreeMap::new()
+    }
 }
 
 /// A type that can apply a filter to a set of preprocessed [FilteredSources] in order to set sparse
@@ -158,13 +577,25 @@ impl<D> SparseOutputFilter<D> {
             .remove("*")
             .unwrap_or_else(OutputSelection::default_file_output_selection);
 
-        let mut full_compilation = HashSet::new();
+        // files which need complete compilation, mapped to the contracts that need output or `None`
+        // if all contracts need output
+        let mut full_compilation = BTreeMap::<PathBuf, Option<BTreeSet<String>>>::new();
 
         // populate sources which need complete compilation with data from filter
         for (file, source) in sources.0.iter() {
             if source.is_dirty() {
+                let mut contracts = f.sparse_contracts(file, graph);
                 for source in f.sparse_sources(file, graph) {
-                    full_compilation.insert(source);
+                    let names = contracts.remove(&source);
+                    match full_compilation.entry(source) {
+                        Entry::Vacant(entry) => {
+                            entry.insert(names);
+                        }
+                        Entry::Occupied(mut entry) => match (entry.get_mut(), names) {
+                            (Some(selected), Some(names)) => selected.extend(names),
+                            (selected, _) => *selected = None,
+                        },
+                    }
                 }
             }
         }
@@ -172,8 +603,12 @@ impl<D> SparseOutputFilter<D> {
         // set output selections
         for file in sources.0.keys() {
             let key = format!("{}", file.display());
-            if full_compilation.contains(file) {
-                settings.output_selection_mut().as_mut().insert(key, selection.clone());
+            if let Some(contracts) = full_compilation.get(file) {
+                let selection = match contracts {
+                    Some(contracts) => contract_output_selection(&selection, contracts),
+                    None => selection.clone(),
+                };
+                settings.output_selection_mut().as_mut().insert(key, selection);
             } else {
                 settings
                     .output_selection_mut()
@@ -220,6 +655,27 @@ impl<D> SparseOutputFilter<D> {
     }
 }
 
+/// Narrows the output `selection` of a file to the given `contracts`.
+///
+/// Outputs selected for all contracts are selected for each of the contracts instead, outputs of
+/// the file itself like the AST are kept.
+fn contract_output_selection(
+    selection: &FileOutputSelection,
+    contracts: &BTreeSet<String>,
+) -> FileOutputSelection {
+    let mut narrowed = selection
+        .iter()
+        .filter(|(name, _)| name.is_empty() || contracts.contains(*name))
+        .map(|(name, outputs)| (name.clone(), outputs.clone()))
+        .collect::<FileOutputSelection>();
+    if let Some(outputs) = selection.get("*") {
+        for contract in contracts {
+            narrowed.entry(contract.clone()).or_insert_with(|| outputs.clone());
+        }
+    }
+    narrowed
+}
+
 impl<D> From<Box<dyn SparseOutputFileFilter<D>>> for SparseOutputFilter<D> {
     fn from(f: Box<dyn SparseOutputFileFilter<D>>) -> Self {
         SparseOutputFilter::Custom(f)
@@ -333,3 +789,124 @@ impl SourceCompilationKind {
         matches!(self, SourceCompilationKind::Complete(_))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{artifacts::Settings, utils, Graph, ProjectPathsConfig, Solc};
+
+    #[test]
+    fn can_match_path_filters() {
+        let filter = PathFilter::dir("./test")
+            .and(PathFilter::exclude("**/fork/**").unwrap())
+            .and(!PathFilter::contract_name("^Invariant").unwrap())
+            .or(PathFilter::glob("src/*.sol").unwrap());
+        assert!(filter.is_match(Path::new("test/Counter.t.sol")));
+        assert!(!filter.is_match(Path::new("/project/test/Counter.t.sol")));
+
+        let rooted = filter.clone().relative_to("/project");
+        assert!(rooted.is_match(Path::new("/project/test/Counter.t.sol")));
+        assert!(rooted.is_match(Path::new("/project/test/unit/Counter.t.sol")));
+        assert!(!rooted.is_match(Path::new("/project/test/fork/Fork.t.sol")));
+        assert!(!rooted.is_match(Path::new("/project/test/InvariantCounter.t.sol")));
+        assert!(rooted.is_match(Path::new("/project/src/Counter.sol")));
+        assert!(!rooted.is_match(Path::new("/project/src/nested/Counter.sol")));
+        assert!(!rooted.is_match(Path::new("/project/contest/Counter.t.sol")));
+        assert!(PathFilter::dir("/project/src").is_match(Path::new("/project/src/a/A.sol")));
+        assert!(!PathFilter::dir("/project/src").is_match(Path::new("/other/src/A.sol")));
+
+        // relative filters don't match directories above the root
+        let project = PathFilter::dir("test")
+            .or(PathFilter::glob("test/**").unwrap())
+            .relative_to("/tmp/test/project");
+        assert!(!project.is_match(Path::new("/tmp/test/project/src/A.sol")));
+        assert!(!project.filter.is_match(Path::new("/tmp/test/project/src/A.sol")));
+        assert!(project.is_match(Path::new("/tmp/test/project/test/A.t.sol")));
+
+        let json = serde_json::to_value(&filter).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "or": [
+                { "and": [
+                    { "dir": "./test" },
+                    { "not": { "glob": "**/fork/**" } },
+                    { "not": { "contractName": "^Invariant" } },
+                ] },
+                { "glob": "src/*.sol" },
+            ] })
+        );
+        assert_eq!(serde_json::from_value::<PathFilter>(json).unwrap(), filter);
+        assert!(serde_json::from_value::<PathFilter>(serde_json::json!({ "glob": "[" })).is_err());
+    }
+
+    #[test]
+    fn can_select_contracts() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = utils::canonicalized(tmp.path());
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("test")).unwrap();
+        fs::write(
+            root.join("src/Counter.sol"),
+            r#"
+import "./Lib.sol";
+contract Base { function deploy() public { new Child(); } }
+contract Child {}
+contract Counter is Base {}
+contract Unused {}
+"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/Lib.sol"),
+            r#"
+library Linked { function f() external {} }
+library Inlined { function f() internal {} }
+"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("test/Counter.t.sol"),
+            r#"
+import "../src/Counter.sol";
+contract CounterTest { function setUp() public { new Counter(); } }
+contract OtherTest {}
+"#,
+        )
+        .unwrap();
+
+        let paths = ProjectPathsConfig::builder()
+            .sources(root.join("src"))
+            .tests(root.join("test"))
+            .no_libs()
+            .build_with_root::<Solc>(&root);
+        let (sources, edges) = Graph::<SolData>::resolve(&paths).unwrap().into_sources();
+
+        let filter = ContractFilter::new([ContractInfo::new("CounterTest")]);
+        assert!(filter.is_match(&root.join("test/Counter.t.sol")));
+        assert!(!filter.is_match(&root.join("src/Counter.sol")));
+
+        let test_file = root.join("test/Counter.t.sol");
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<BTreeSet<_>>();
+        assert_eq!(
+            filter.sparse_contracts(&test_file, &edges),
+            BTreeMap::from([
+                (test_file.clone(), names(&["CounterTest"])),
+                (root.join("src/Counter.sol"), names(&["Child", "Counter"])),
+                (root.join("src/Lib.sol"), names(&["Linked"])),
+            ])
+        );
+        assert!(filter.sparse_sources(&root.join("src/Counter.sol"), &edges).is_empty());
+
+        let mut settings = Settings::default();
+        let sources = FilteredSources::from(sources);
+        SparseOutputFilter::Custom(Box::new(filter)).sparse_sources(sources, &mut settings, &edges);
+        let selection = settings.output_selection.as_ref();
+        let selected = |file: &str| {
+            selection[&root.join(file).display().to_string()].keys().cloned().collect::<Vec<_>>()
+        };
+        assert_eq!(selected("test/Counter.t.sol"), vec!["", "CounterTest"]);
+        assert_eq!(selected("src/Counter.sol"), vec!["", "Child", "Counter"]);
+        assert_eq!(selected("src/Lib.sol"), vec!["", "Linked"]);
+        assert!(!selection.contains_key("*"));
+    }
+}
@@ -1,18 +1,25 @@
 use crate::{
     artifacts::{
-        ast::SourceLocation,
+        ast::{Ast, SourceLocation},
         visitor::{Visitor, Walk},
         ContractDefinitionPart, Error, ExternalInlineAssemblyReference, Identifier, IdentifierPath,
-        MemberAccess, Source, SourceUnit, SourceUnitPart, Sources,
+        MemberAccess, Source, SourceFile, SourceUnit, SourceUnitPart, Sources,
     },
     error::SolcError,
-    resolver::parse::SolData,
-    utils, ConfigurableArtifacts, Graph, Project, ProjectCompileOutput, ProjectPathsConfig, Result,
+    resolver::{
+        parse::{SolData, SolDataUnit, SolDefinition, SolImport},
+        SolImportAlias,
+    },
+    utils, ArtifactOutput, ConfigurableContractArtifact, Graph, Project, ProjectCompileOutput,
+    ProjectPathsConfig, Result, Solc,
 };
 use itertools::Itertools;
+use serde::Deserialize;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{btree_map, BTreeMap, HashMap, HashSet},
+    fmt,
     hash::Hash,
+    ops::Range,
     path::{Path, PathBuf},
 };
 
@@ -96,71 +103,298 @@ impl Visitor for ReferencesCollector {
 /// source_path -> (start, end, new_value)
 type Updates = HashMap<PathBuf, HashSet<(usize, usize, String)>>;
 
+/// A source as it is written to the flattened file.
+struct FlattenedSource<'a> {
+    path: &'a Path,
+    /// The comment with the path of the source that precedes its content
+    header: String,
+    /// The content of the source without imports and with all renames applied
+    content: String,
+    /// The updates applied to the original content, sorted by their position
+    edits: Vec<Edit>,
+}
+
+/// A replacement of `start..end` of an original source with the content at `new_start..new_end`
+/// of the updated source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Edit {
+    start: usize,
+    end: usize,
+    new_start: usize,
+    new_end: usize,
+}
+
 pub struct FlatteningResult<'a> {
     /// Updated source in the order they shoud be written to the output file.
-    sources: Vec<String>,
+    sources: Vec<FlattenedSource<'a>>,
     /// Pragmas that should be present in the target file.
     pragmas: Vec<String>,
     /// License identifier that should be present in the target file.
-    license: Option<&'a str>,
+    license: Option<String>,
+    /// Original content of the sources, by path.
+    originals: &'a Sources,
 }
 
 impl<'a> FlatteningResult<'a> {
     fn new(
-        flattener: &Flattener,
+        flattener: &'a Flattener,
         mut updates: Updates,
         pragmas: Vec<String>,
-        license: Option<&'a str>,
+        license: Option<String>,
     ) -> Self {
         let mut sources = Vec::new();
 
         for path in &flattener.ordered_sources {
             let mut content = flattener.sources.get(path).unwrap().content.as_bytes().to_vec();
             let mut offset: isize = 0;
+            let mut edits = Vec::new();
             if let Some(updates) = updates.remove(path) {
                 let mut updates = updates.iter().collect::<Vec<_>>();
                 updates.sort_by_key(|(start, _, _)| *start);
                 for (start, end, new_value) in updates {
-                    let start = (*start as isize + offset) as usize;
-                    let end = (*end as isize + offset) as usize;
+                    let new_start = (*start as isize + offset) as usize;
+                    let new_end = (*end as isize + offset) as usize;
 
-                    content.splice(start..end, new_value.bytes());
+                    content.splice(new_start..new_end, new_value.bytes());
                     offset += new_value.len() as isize - (end - start) as isize;
+                    edits.push(Edit {
+                        start: *start,
+                        end: *end,
+                        new_start,
+                        new_end: new_start + new_value.len(),
+                    });
                 }
             }
-            let content = format!(
-                "// {}\n{}",
-                path.strip_prefix(&flattener.project_root).unwrap_or(path).display(),
-                String::from_utf8(content).unwrap()
-            );
-            sources.push(content);
+            sources.push(FlattenedSource {
+                path,
+                header: format!(
+                    "// {}",
+                    path.strip_prefix(&flattener.project_root).unwrap_or(path).display()
+                ),
+                content: String::from_utf8(content).unwrap(),
+                edits,
+            });
         }
 
-        Self { sources, pragmas, license }
+        Self { sources, pragmas, license, originals: &flattener.sources }
     }
 
-    fn get_flattened_target(&self) -> String {
-        let mut result = String::new();
+    /// Returns the flattened source.
+    pub fn get_flattened_target(&self) -> String {
+        format!("{}\n", self.lines().into_iter().map(|(line, _)| line).format("\n"))
+    }
+
+    /// Returns the mapping from the lines of the flattened source to the original sources.
+    pub fn source_map(&self) -> FlattenedSourceMap {
+        let lines = self.lines().into_iter().map(|(_, origin)| origin).collect();
+        let sources = self
+            .sources
+            .iter()
+            .map(|source| {
+                let original = &self.originals.get(source.path).unwrap().content;
+                let line_starts = std::iter::once(0)
+                    .chain(original.match_indices('\n').map(|(idx, _)| idx + 1))
+                    .collect();
+                MappedSource {
+                    path: source.path.to_path_buf(),
+                    edits: source.edits.clone(),
+                    line_starts,
+                }
+            })
+            .collect();
+        FlattenedSourceMap { lines, sources }
+    }
+
+    /// Returns the lines of the flattened source, together with the index of the source and the
+    /// offset within the updated source they originate from.
+    ///
+    /// Sources are separated by an empty line, runs of empty lines are collapsed into one and
+    /// leading and trailing whitespace is removed.
+    fn lines(&self) -> Vec<(String, Option<(usize, usize)>)> {
+        // the output is built from pieces, which are either part of a source or not
+        let mut text = String::new();
+        let mut pieces = Vec::<(usize, Option<usize>)>::new();
+        let mut push = |text: &mut String, piece: &str, source: Option<usize>| {
+            pieces.push((text.len(), source));
+            text.push_str(piece);
+        };
 
         if let Some(license) = &self.license {
-            result.push_str(&format!("{}\n", license));
+            push(&mut text, &format!("{license}\n"), None);
         }
         for pragma in &self.pragmas {
-            result.push_str(&format!("{}\n", pragma));
+            push(&mut text, &format!("{pragma}\n"), None);
         }
-        for source in &self.sources {
-            result.push_str(&format!("\n\n{}", source));
+        for (idx, source) in self.sources.iter().enumerate() {
+            push(&mut text, &format!("\n\n{}\n", source.header), None);
+            push(&mut text, &source.content, Some(idx));
         }
 
-        format!("{}\n", utils::RE_THREE_OR_MORE_NEWLINES.replace_all(&result, "\n\n").trim())
+        let mut lines = Vec::<(String, Option<(usize, usize)>)>::new();
+        let mut line_start = 0;
+        for line in text.split('\n') {
+            // every line starts within a single piece, since all other pieces end with a newline
+            let (piece_start, source) =
+                pieces[pieces.partition_point(|(start, _)| *start <= line_start).saturating_sub(1)];
+            let origin = source.map(|idx| (idx, line_start - piece_start));
+            line_start += line.len() + 1;
+
+            if line.is_empty() && lines.last().map_or(false, |(last, _)| last.is_empty()) {
+                continue;
+            }
+            lines.push((line.to_string(), origin));
+        }
+
+        while lines.first().map_or(false, |(line, _)| line.trim().is_empty()) {
+            lines.remove(0);
+        }
+        while lines.last().map_or(false, |(line, _)| line.trim().is_empty()) {
+            lines.pop();
+        }
+        if let Some((line, origin)) = lines.first_mut() {
+            let trimmed = line.trim_start().to_string();
+            if let Some((_, offset)) = origin {
+                *offset += line.len() - trimmed.len();
+            }
+            *line = trimmed;
+        }
+        if let Some((line, _)) = lines.last_mut() {
+            line.truncate(line.trim_end().len());
+        }
+        lines
     }
 }
 
-/// Context for flattening. Stores all sources and ASTs that are in scope of the flattening target.
+/// A source of a [FlattenedSourceMap]
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct MappedSource {
+    path: PathBuf,
+    edits: Vec<Edit>,
+    /// Byte offsets of the starts of all lines of the original source
+    line_starts: Vec<usize>,
+}
+
+impl MappedSource {
+    /// Maps an offset of the updated source to the original source.
+    ///
+    /// Offsets within renamed identifiers map to the same position of the original identifier,
+    /// clamped to its end, and the position of removed content maps to its start.
+    fn original_offset(&self, offset: usize) -> usize {
+        let idx = self.edits.partition_point(|edit| edit.new_start <= offset);
+        let Some(edit) = idx.checked_sub(1).map(|idx| &self.edits[idx]) else { return offset };
+        if offset < edit.new_end || offset == edit.new_start {
+            edit.start + (offset - edit.new_start).min(edit.end - edit.start)
+        } else {
+            offset - edit.new_end + edit.end
+        }
+    }
+
+    /// Returns the 0-based line of the original offset
+    fn line(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|start| *start <= offset) - 1
+    }
+}
+
+/// A range of lines of a flattened file that originates from consecutive lines of a source file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FlattenedLineRange {
+    /// The 0-based lines of the flattened file
+    pub lines: Range<usize>,
+    /// The path of the original source file
+    pub path: PathBuf,
+    /// The byte offset of the first line in the original source file
+    pub offset: usize,
+    /// The 0-based line of the first line in the original source file
+    pub line: usize,
+}
+
+/// Maps lines and positions of a flattened file back to the original source files.
+///
+/// Since the flattener renames identifiers and removes imports, pragmas and license identifiers,
+/// positions are mapped through the applied updates, see [`Self::original_location()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FlattenedSourceMap {
+    /// The source index and the offset within the updated source of every line of the output
+    lines: Vec<Option<(usize, usize)>>,
+    sources: Vec<MappedSource>,
+}
+
+impl FlattenedSourceMap {
+    /// Returns the ranges of lines of the flattened file that originate from consecutive lines of
+    /// an original source file, in order.
+    ///
+    /// Lines that were added by the flattener, like the combined pragmas or the comments with the
+    /// paths of the sources, are not part of any range.
+    pub fn ranges(&self) -> Vec<FlattenedLineRange> {
+        let mut ranges = Vec::<FlattenedLineRange>::new();
+        let mut prev: Option<(usize, usize)> = None;
+        for (idx, origin) in self.lines.iter().enumerate() {
+            let Some((source_idx, offset)) = *origin else {
+                prev = None;
+                continue;
+            };
+            let source = &self.sources[source_idx];
+            let offset = source.original_offset(offset);
+            let line = source.line(offset);
+            match ranges.last_mut() {
+                Some(range) if prev == Some((source_idx, line.wrapping_sub(1))) => {
+                    range.lines.end = idx + 1;
+                }
+                _ => ranges.push(FlattenedLineRange {
+                    lines: idx..idx + 1,
+                    path: source.path.clone(),
+                    offset,
+                    line,
+                }),
+            }
+            prev = Some((source_idx, line));
+        }
+        ranges
+    }
+
+    /// Returns the original source file and byte offset of the given 0-based line and byte column
+    /// of the flattened file.
+    ///
+    /// Returns `None` if the line was added by the flattener.
+    pub fn original_location(&self, line: usize, column: usize) -> Option<(&Path, usize)> {
+        let (source_idx, offset) = (*self.lines.get(line)?)?;
+        let source = &self.sources[source_idx];
+        Some((&source.path, source.original_offset(offset + column)))
+    }
+}
+
+/// Artifacts that can include the AST of their source file, which the [`Flattener`] is built from.
+pub trait ArtifactAst {
+    /// Returns the AST of the source file, if it was part of the output selection
+    fn ast(&self) -> Option<&Ast>;
+}
+
+impl ArtifactAst for ConfigurableContractArtifact {
+    fn ast(&self) -> Option<&Ast> {
+        self.ast.as_ref()
+    }
+}
+
+/// How the SPDX license identifiers of the flattened sources are merged, see
+/// [`Flattener::with_license_merging()`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LicenseMerging {
+    /// Keeps only the license identifier of the first target that has one
+    #[default]
+    Target,
+    /// Combines the distinct license identifiers of all sources into a single `AND` expression
+    And,
+    /// Keeps the license identifier of the first target that has one and the license of every
+    /// source as a plain `// License: <id>` comment in its section, since solc rejects multiple
+    /// SPDX license identifiers in a file
+    PerSource,
+}
+
+/// Context for flattening. Stores all sources and ASTs that are in scope of the flattening targets.
 pub struct Flattener {
-    /// Target file to flatten.
-    target: PathBuf,
-    /// Sources including only target and it dependencies (imports of any depth).
+    /// Target files to flatten.
+    targets: Vec<PathBuf>,
+    /// Sources including only targets and their dependencies (imports of any depth).
     sources: Sources,
     /// Vec of (path, ast) pairs.
     asts: Vec<(PathBuf, SourceUnit)>,
@@ -168,28 +402,158 @@ pub struct Flattener {
     ordered_sources: Vec<PathBuf>,
     /// Project root directory.
     project_root: PathBuf,
+    /// How the license identifiers of the sources are merged.
+    license_merging: LicenseMerging,
 }
 
 impl Flattener {
-    /// Compilation output is expected to contain all artifacts for all sources.
+    /// Compilation output is expected to contain the ASTs of the target and all its dependencies.
     /// Flattener caller is expected to resolve all imports of target file, compile them and pass
     /// into this function.
-    pub fn new(
-        project: &Project,
-        output: &ProjectCompileOutput<Error, ConfigurableArtifacts>,
+    ///
+    /// The ASTs are taken from the compiler output of freshly compiled sources and from the cached
+    /// and fresh artifacts, which works with any [`ArtifactOutput`] whose artifacts include the
+    /// AST, see [`ArtifactAst`]. AST node ids are only unique within a single compiler run, so the
+    /// ASTs are grouped by compiler version and the target and all its dependencies must have been
+    /// compiled with the same version.
+    pub fn new<T: ArtifactOutput>(
+        project: &Project<Solc, T>,
+        output: &ProjectCompileOutput<Error, T>,
         target: &Path,
+    ) -> Result<Self>
+    where
+        T::Artifact: ArtifactAst,
+    {
+        Self::new_multi(project, output, [target])
+    }
+
+    /// Same as [`Self::new()`] but flattens multiple targets into a single file.
+    ///
+    /// The dependencies of all targets are merged, so every source is included only once, and
+    /// duplicate names are renamed consistently across all targets. The pragmas of all sources are
+    /// combined and the license identifier of the first target that has one is kept.
+    pub fn new_multi<T: ArtifactOutput>(
+        project: &Project<Solc, T>,
+        output: &ProjectCompileOutput<Error, T>,
+        targets: impl IntoIterator<Item = impl AsRef<Path>>,
+    ) -> Result<Self>
+    where
+        T::Artifact: ArtifactAst,
+    {
+        let root = project.root();
+        // there's one compiler run per version
+        let mut compilations = BTreeMap::<_, BTreeMap<_, _>>::new();
+        for (path, source, version) in output.output().sources.sources_with_version() {
+            if let Some(ast) = &source.ast {
+                compilations
+                    .entry(version)
+                    .or_default()
+                    .insert(root.join(path), serde_json::from_value(serde_json::to_value(ast)?)?);
+            }
+        }
+        // all sources of a file are cached if one of them is, so the ASTs of cached artifacts are
+        // only needed for the files that weren't compiled
+        for (path, contracts) in output.cached_artifacts().iter().chain(output.compiled_artifacts())
+        {
+            for artifact in contracts.values().flatten() {
+                let Some(ast) = artifact.artifact.ast() else { continue };
+                let asts = compilations.entry(&artifact.version).or_default();
+                if let btree_map::Entry::Vacant(entry) = asts.entry(root.join(path)) {
+                    entry.insert(serde_json::from_value(serde_json::to_value(ast)?)?);
+                }
+            }
+        }
+
+        Self::from_asts(&project.paths, compilations.into_values().collect(), targets)
+    }
+
+    /// Creates a flattener from the ASTs of the given build info files, without a compiled
+    /// project.
+    ///
+    /// Directories are searched for build info files, like the `build-info` dir of the artifacts.
+    /// Relative source paths of the build infos are resolved against the root of `paths`.
+    ///
+    /// The targets and all their dependencies must be part of the same build info, since AST node
+    /// ids are only unique within a single compiler run.
+    pub fn from_build_infos(
+        paths: &ProjectPathsConfig,
+        build_infos: impl IntoIterator<Item = impl AsRef<Path>>,
+        targets: impl IntoIterator<Item = impl AsRef<Path>>,
     ) -> Result<Self> {
-        let input_files = output
-            .artifacts_with_files()
-            .map(|(file, _, _)| PathBuf::from(file))
+        /// The part of a build info file that contains the ASTs.
+        #[derive(Deserialize)]
+        struct BuildInfoAsts {
+            output: BuildInfoOutput,
+        }
+
+        #[derive(Deserialize)]
+        struct BuildInfoOutput {
+            #[serde(default)]
+            sources: BTreeMap<PathBuf, SourceFile>,
+        }
+
+        let mut files = Vec::new();
+        for path in build_infos {
+            let path = path.as_ref();
+            if path.is_dir() {
+                let mut dir_files = utils::source_files(path, &["json"]);
+                dir_files.sort();
+                files.extend(dir_files);
+            } else {
+                files.push(path.to_path_buf());
+            }
+        }
+
+        let mut compilations = Vec::with_capacity(files.len());
+        for file in files {
+            let build_info: BuildInfoAsts = utils::read_json_file(&file)?;
+            let mut asts = BTreeMap::new();
+            for (path, source) in build_info.output.sources {
+                if let Some(ast) = source.ast {
+                    asts.insert(
+                        paths.root.join(path),
+                        serde_json::from_value(serde_json::to_value(ast)?)?,
+                    );
+                }
+            }
+            compilations.push(asts);
+        }
+
+        Self::from_asts(paths, compilations, targets)
+    }
+
+    /// Resolves the sources of the targets and pairs them with the ASTs of the first compilation
+    /// that contains all of them.
+    ///
+    /// ASTs of different compilations are never mixed, because their node ids collide.
+    fn from_asts(
+        paths: &ProjectPathsConfig,
+        compilations: Vec<BTreeMap<PathBuf, SourceUnit>>,
+        targets: impl IntoIterator<Item = impl AsRef<Path>>,
+    ) -> Result<Self> {
+        let targets = targets.into_iter().map(|t| t.as_ref().to_path_buf()).collect::<Vec<_>>();
+        if targets.is_empty() {
+            return Err(SolcError::msg("no targets to flatten"));
+        }
+
+        let input_files = compilations
+            .iter()
+            .flat_map(BTreeMap::keys)
+            .chain(&targets)
+            .filter(|path| path.is_file())
+            .cloned()
             .collect::<HashSet<_>>()
             .into_iter()
             .collect::<Vec<_>>();
 
         let sources = Source::read_all_files(input_files)?;
-        let graph = Graph::resolve_sources(&project.paths, sources)?;
+        let graph = Graph::resolve_sources(paths, sources)?;
 
-        let ordered_sources = collect_ordered_deps(&target.to_path_buf(), &project.paths, &graph)?;
+        let ordered_sources = if let [target] = &targets[..] {
+            collect_ordered_deps(target, paths, &graph)?
+        } else {
+            collect_ordered_deps_of_all(&targets, paths, &graph)?
+        };
 
         #[cfg(windows)]
         let ordered_sources = {
@@ -201,30 +565,41 @@ impl Flattener {
             sources
         };
 
+        let mut asts = compilations
+            .into_iter()
+            .find(|asts| ordered_sources.iter().all(|path| asts.contains_key(path)))
+            .ok_or_else(|| {
+                SolcError::msg(
+                    "the ASTs of the targets and their dependencies must come from a single \
+                     compilation, compile them together",
+                )
+            })?;
+
         let sources = Source::read_all(&ordered_sources)?;
 
-        // Convert all ASTs from artifacts to strongly typed ASTs
-        let mut asts: Vec<(PathBuf, SourceUnit)> = Vec::new();
-        for (path, ast) in output.artifacts_with_files().filter_map(|(path, _, artifact)| {
-            if let Some(ast) = artifact.ast.as_ref() {
-                if sources.contains_key(Path::new(path)) {
-                    return Some((path, ast));
-                }
-            }
-            None
-        }) {
-            asts.push((PathBuf::from(path), serde_json::from_str(&serde_json::to_string(ast)?)?));
-        }
+        let asts = ordered_sources
+            .iter()
+            .map(|path| (path.clone(), asts.remove(path).expect("checked above")))
+            .collect();
 
         Ok(Flattener {
-            target: target.into(),
+            targets,
             sources,
             asts,
             ordered_sources,
-            project_root: project.root().clone(),
+            project_root: paths.root.clone(),
+            license_merging: LicenseMerging::default(),
         })
     }
 
+    /// Sets how the license identifiers of the sources are merged, by default only the license of
+    /// the target is kept.
+    #[must_use]
+    pub fn with_license_merging(mut self, license_merging: LicenseMerging) -> Self {
+        self.license_merging = license_merging;
+        self
+    }
+
     /// Flattens target file and returns the result as a string
     ///
     /// Flattening process includes following steps:
@@ -232,9 +607,15 @@ impl Flattener {
     ///    imports.
     /// 2. Find all duplicates among file-level definitions and rename them to avoid conflicts.
     /// 3. Remove all imports.
-    /// 4. Remove all pragmas except for the ones in the target file.
-    /// 5. Remove all license identifiers except for the one in the target file.
+    /// 4. Remove all pragmas and combine them into the pragmas of the flattened file.
+    /// 5. Remove all license identifiers and merge them according to [LicenseMerging].
     pub fn flatten(&self) -> String {
+        self.flattening_result().get_flattened_target()
+    }
+
+    /// Same as [`Self::flatten()`] but returns the [FlatteningResult], which also provides a
+    /// [FlattenedSourceMap] to map positions in the flattened file back to the original sources.
+    pub fn flattening_result(&self) -> FlatteningResult<'_> {
         let mut updates = Updates::new();
 
         let top_level_names = self.rename_top_level_definitions(&mut updates);
@@ -246,15 +627,6 @@ impl Flattener {
         let target_pragmas = self.process_pragmas(&mut updates);
         let target_license = self.process_licenses(&mut updates);
 
-        self.flatten_result(updates, target_pragmas, target_license).get_flattened_target()
-    }
-
-    fn flatten_result<'a>(
-        &'a self,
-        updates: Updates,
-        target_pragmas: Vec<String>,
-        target_license: Option<&'a str>,
-    ) -> FlatteningResult<'_> {
         FlatteningResult::new(self, updates, target_pragmas, target_license)
     }
 
@@ -369,7 +741,7 @@ impl Flattener {
         for (path, ast) in &self.asts {
             for node in &ast.nodes {
                 let mut collector =
-                    ReferencesCollector { path: self.target.clone(), references: HashMap::new() };
+                    ReferencesCollector { path: path.clone(), references: HashMap::new() };
 
                 node.walk(&mut collector);
 
@@ -654,24 +1026,15 @@ impl Flattener {
             .collect()
     }
 
-    /// Removes all pragma directives from all sources. Returns Vec with experimental and combined
-    /// version pragmas (if present).
+    /// Removes all pragma directives from all sources. Returns the pragmas of the flattened file,
+    /// see [`merge_pragmas()`].
     fn process_pragmas(&self, updates: &mut Updates) -> Vec<String> {
-        let mut experimental = None;
-
-        let pragmas = self.collect_pragmas();
-        let mut version_pragmas = Vec::new();
+        let mut pragmas = self.collect_pragmas().into_iter().collect::<Vec<_>>();
+        pragmas.sort_by_key(|loc| {
+            (self.ordered_sources.iter().position(|p| *p == loc.path), loc.start)
+        });
 
         for loc in &pragmas {
-            let pragma_content = self.read_location(loc);
-            if pragma_content.contains("experimental") {
-                if experimental.is_none() {
-                    experimental = Some(self.read_location(loc).to_string());
-                }
-            } else if pragma_content.contains("solidity") {
-                version_pragmas.push(pragma_content);
-            }
-
             updates.entry(loc.path.clone()).or_default().insert((
                 loc.start,
                 loc.end,
@@ -679,17 +1042,7 @@ impl Flattener {
             ));
         }
 
-        let mut pragmas = Vec::new();
-
-        if let Some(version_pragma) = combine_version_pragmas(version_pragmas) {
-            pragmas.push(version_pragma);
-        }
-
-        if let Some(pragma) = experimental {
-            pragmas.push(pragma);
-        }
-
-        pragmas
+        merge_pragmas(pragmas.iter().map(|loc| self.read_location(loc)))
     }
 
     // Collects all pragma directives locations.
@@ -707,23 +1060,27 @@ impl Flattener {
             .collect()
     }
 
-    /// Removes all license identifiers from all sources. Returns licesnse identifier from target
-    /// file, if any.
-    fn process_licenses(&self, updates: &mut Updates) -> Option<&str> {
-        let mut target_license = None;
+    /// Removes all license identifiers from all sources. Returns the license identifier of the
+    /// flattened file, depending on the [LicenseMerging].
+    fn process_licenses(&self, updates: &mut Updates) -> Option<String> {
+        // targets first, then all other sources in the order they are written
+        let mut licenses = self.collect_licenses().into_iter().collect::<Vec<_>>();
+        licenses.sort_by_key(|loc| {
+            (
+                self.targets.iter().position(|target| *target == loc.path).unwrap_or(usize::MAX),
+                self.ordered_sources.iter().position(|p| *p == loc.path),
+            )
+        });
 
-        for loc in &self.collect_licenses() {
-            if loc.path == self.target {
-                target_license = Some(self.read_location(loc));
-            }
-            updates.entry(loc.path.clone()).or_default().insert((
-                loc.start,
-                loc.end,
-                "".to_string(),
-            ));
+        for loc in &licenses {
+            let replacement = license_replacement(self.read_location(loc), self.license_merging);
+            updates.entry(loc.path.clone()).or_default().insert((loc.start, loc.end, replacement));
         }
 
-        target_license
+        merge_licenses(
+            licenses.iter().map(|loc| (self.read_location(loc), self.targets.contains(&loc.path))),
+            self.license_merging,
+        )
     }
 
     // Collects all SPDX-License-Identifier locations.
@@ -731,17 +1088,11 @@ impl Flattener {
         self.sources
             .iter()
             .flat_map(|(path, source)| {
-                let mut licenses = HashSet::new();
-                if let Some(license_start) = source.content.find("SPDX-License-Identifier:") {
-                    let start =
-                        source.content[..license_start].rfind('\n').map(|i| i + 1).unwrap_or(0);
-                    let end = start
-                        + source.content[start..]
-                            .find('\n')
-                            .unwrap_or(source.content.len() - start);
-                    licenses.insert(ItemLocation { path: path.clone(), start, end });
-                }
-                licenses
+                license_location(&source.content).map(|range| ItemLocation {
+                    path: path.clone(),
+                    start: range.start,
+                    end: range.end,
+                })
             })
             .collect()
     }
@@ -753,6 +1104,111 @@ impl Flattener {
     }
 }
 
+/// Returns the license expression of a `SPDX-License-Identifier` comment line.
+fn license_identifier(line: &str) -> &str {
+    let identifier = line.split_once("SPDX-License-Identifier:").map_or(line, |(_, id)| id);
+    identifier.trim().trim_end_matches("*/").trim()
+}
+
+/// Returns the range of the line with the `SPDX-License-Identifier` of a source, excluding the
+/// line break.
+fn license_location(content: &str) -> Option<Range<usize>> {
+    let license_start = content.find("SPDX-License-Identifier:")?;
+    let start = content[..license_start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let end = start + content[start..].find('\n').unwrap_or(content.len() - start);
+    Some(start..end)
+}
+
+/// Returns the replacement of the license line of a source in the flattened file.
+fn license_replacement(line: &str, license_merging: LicenseMerging) -> String {
+    match license_merging {
+        LicenseMerging::PerSource => format!("// License: {}", license_identifier(line)),
+        LicenseMerging::Target | LicenseMerging::And => "".to_string(),
+    }
+}
+
+/// Returns the license line of the flattened file.
+///
+/// `licenses` are the license lines of the sources, paired with whether the source is a target,
+/// with the targets first and all other sources in the order they are written.
+fn merge_licenses<'a>(
+    licenses: impl IntoIterator<Item = (&'a str, bool)>,
+    license_merging: LicenseMerging,
+) -> Option<String> {
+    let mut licenses = licenses.into_iter();
+    match license_merging {
+        LicenseMerging::Target | LicenseMerging::PerSource => {
+            licenses.find(|(_, is_target)| *is_target).map(|(line, _)| line.to_string())
+        }
+        LicenseMerging::And => {
+            let mut identifiers = Vec::new();
+            for (line, _) in licenses {
+                let identifier = license_identifier(line);
+                if !identifier.is_empty() && !identifiers.contains(&identifier) {
+                    identifiers.push(identifier);
+                }
+            }
+            // `AND` binds tighter than `OR`, so compound expressions must be parenthesized
+            let expression = identifiers
+                .iter()
+                .map(|id| if id.contains(' ') { format!("({id})") } else { id.to_string() })
+                .join(" AND ");
+            (!identifiers.is_empty()).then(|| format!("// SPDX-License-Identifier: {expression}"))
+        }
+    }
+}
+
+/// Combines the pragma directives of all sources, in the order they are written, into the pragmas
+/// of the flattened file: the combined version pragma, the ABI coder pragma and the experimental
+/// pragmas (if present).
+///
+/// ABI coder v2 is selected if any source requires it, either via `pragma abicoder v2` or
+/// `pragma experimental ABIEncoderV2`, and the first of these pragmas is kept. All other
+/// experimental pragmas are kept once.
+fn merge_pragmas<'a>(pragmas: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut version_pragmas = Vec::new();
+    let mut abicoder_v1 = None;
+    let mut abicoder_v2 = None;
+    let mut experimental = Vec::new();
+
+    for pragma_content in pragmas {
+        let directive = pragma_content.trim().trim_end_matches(';');
+        let directive = directive.strip_prefix("pragma").unwrap_or(directive);
+        // the version may directly follow the name, like `pragma solidity>=0.8.0;`
+        if directive.trim_start().starts_with("solidity") {
+            version_pragmas.push(pragma_content);
+            continue;
+        }
+        let literals = directive.split_whitespace().collect::<Vec<_>>();
+        match literals[..] {
+            ["abicoder", "v1"] => {
+                abicoder_v1.get_or_insert(pragma_content);
+            }
+            ["abicoder", "v2"] | ["experimental", "ABIEncoderV2"] => {
+                abicoder_v2.get_or_insert(pragma_content);
+            }
+            ["experimental", ..] if !experimental.contains(&pragma_content) => {
+                experimental.push(pragma_content);
+            }
+            _ => {}
+        }
+    }
+
+    let mut pragmas = Vec::new();
+
+    if let Some(version_pragma) = combine_version_pragmas(version_pragmas) {
+        pragmas.push(version_pragma);
+    }
+
+    if let Some(pragma) = abicoder_v2.or(abicoder_v1) {
+        pragmas.push(pragma.to_string());
+    }
+
+    pragmas.extend(experimental.into_iter().map(str::to_string));
+
+    pragmas
+}
+
 /// Performs DFS to collect all dependencies of a target
 fn collect_deps<C>(
     path: &PathBuf,
@@ -817,6 +1273,32 @@ pub fn collect_ordered_deps<C>(
     Ok(ordered_deps)
 }
 
+/// Same as [collect_ordered_deps] but for multiple targets.
+///
+/// The dependencies of all targets are merged and sorted by the number of their dependencies, so
+/// every file is placed after its dependencies, unless there are cyclic imports.
+pub fn collect_ordered_deps_of_all<C>(
+    targets: &[PathBuf],
+    paths: &ProjectPathsConfig<C>,
+    graph: &Graph,
+) -> Result<Vec<PathBuf>> {
+    let mut deps = HashSet::new();
+    for target in targets {
+        collect_deps(target, paths, graph, &mut deps)?;
+    }
+
+    let mut paths_with_deps_count = Vec::new();
+    for path in deps {
+        let mut path_deps = HashSet::new();
+        collect_deps(&path, paths, graph, &mut path_deps)?;
+        paths_with_deps_count.push((path_deps.len(), path));
+    }
+
+    paths_with_deps_count.sort();
+
+    Ok(paths_with_deps_count.into_iter().map(|(_, path)| path).collect())
+}
+
 pub fn combine_version_pragmas(pragmas: Vec<&str>) -> Option<String> {
     let mut versions = pragmas
         .into_iter()
@@ -840,3 +1322,556 @@ pub fn combine_version_pragmas(pragmas: Vec<&str>) -> Option<String> {
 
     None
 }
+
+/// Replaces the aliases of `import {A as B}` imports with the original names, since the imports
+/// are removed from the flattened source.
+pub(crate) fn replace_import_aliases(content: &mut String, imports: &[SolDataUnit<SolImport>]) {
+    // Iterate over all aliased imports, and replace alias with real name via regexes
+    for alias in imports.iter().flat_map(|i| i.data().aliases()) {
+        let (alias, target) = match alias {
+            SolImportAlias::Contract(alias, target) => (alias.clone(), target.clone()),
+            _ => continue,
+        };
+        let name_regex = utils::create_contract_or_lib_name_regex(&alias);
+        let target_len = target.len() as isize;
+        let mut replace_offset = 0;
+        for cap in name_regex.captures_iter(&content.clone()) {
+            if cap.name("ignore").is_some() {
+                continue;
+            }
+            if let Some(name_match) = ["n1", "n2", "n3"].iter().find_map(|name| cap.name(name)) {
+                let name_match_range = utils::range_by_offset(&name_match.range(), replace_offset);
+                replace_offset += target_len - (name_match_range.len() as isize);
+                content.replace_range(name_match_range, &target);
+            }
+        }
+    }
+}
+
+/// A top-level name that's defined in more than one of the flattened sources.
+///
+/// The flattened source doesn't compile until the collision is resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameCollision {
+    /// The colliding name
+    pub name: String,
+    /// The sources defining the name, in the order they appear in the flattened source
+    pub paths: Vec<PathBuf>,
+}
+
+impl fmt::Display for NameCollision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is defined in {}",
+            self.name,
+            self.paths.iter().map(|p| p.display()).format(", ")
+        )
+    }
+}
+
+/// The result of [`flatten_syntactic()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntacticFlattening {
+    /// The flattened source
+    pub source: String,
+    /// The top-level names that are defined in more than one source, sorted by name
+    pub collisions: Vec<NameCollision>,
+}
+
+impl SyntacticFlattening {
+    /// Returns `true` if the flattened source contains colliding top-level names
+    pub fn has_collisions(&self) -> bool {
+        !self.collisions.is_empty()
+    }
+}
+
+/// Flattens the target without compiling it, based on the parsed sources only.
+///
+/// This is a degraded alternative to [`Flattener`] for projects that don't compile, or whose
+/// compiler version isn't available. The dependencies are ordered with [`collect_ordered_deps()`],
+/// imports are removed and aliased imports are replaced with the original names. Pragmas and
+/// license identifiers are merged like [`Flattener`] does, according to `license_merging`.
+///
+/// Without an AST, colliding top-level names can't be renamed, so they are reported in
+/// [`SyntacticFlattening::collisions`] instead. Qualified imports like `import "./A.sol" as A` are
+/// left as is.
+pub fn flatten_syntactic(
+    paths: &ProjectPathsConfig,
+    target: &Path,
+    license_merging: LicenseMerging,
+) -> Result<SyntacticFlattening> {
+    let mut input_files = paths.input_files();
+    if !input_files.iter().any(|file| file == target) {
+        input_files.push(target.to_path_buf());
+    }
+
+    let sources = Source::read_all_files(input_files)?;
+    let graph = Graph::<SolData>::resolve_sources(paths, sources)?;
+    let ordered_deps = collect_ordered_deps(&target.to_path_buf(), paths, &graph)?;
+
+    #[cfg(windows)]
+    let ordered_deps = {
+        use path_slash::PathBufExt;
+
+        let mut deps = ordered_deps;
+        for p in &mut deps {
+            *p = PathBuf::from(p.to_slash_lossy().to_string());
+        }
+        deps
+    };
+
+    let mut licenses = Vec::new();
+    let mut pragmas = Vec::new();
+    let mut definitions = BTreeMap::<&str, Vec<(&PathBuf, &SolDefinition)>>::new();
+    let mut sources = Vec::new();
+
+    for path in &ordered_deps {
+        let node_id = graph
+            .files()
+            .get(path)
+            .ok_or_else(|| SolcError::msg(format!("cannot resolve file at {}", path.display())))?;
+        let node = graph.node(*node_id);
+        let data = &node.data;
+        let content = node.content();
+
+        let mut replacements = Vec::new();
+        if let Some(range) = license_location(content) {
+            let line = &content[range.clone()];
+            licenses.push((line, path == target));
+            replacements.push((range, license_replacement(line, license_merging)));
+        }
+        for pragma in &data.pragmas {
+            pragmas.push(pragma.data().as_str());
+            replacements.push((pragma.loc(), String::new()));
+        }
+        replacements.extend(data.imports.iter().map(|import| (import.loc(), String::new())));
+
+        for definition in &data.definitions {
+            definitions.entry(&definition.data().name).or_default().push((path, definition.data()));
+        }
+
+        let mut content = content.to_string();
+        replacements.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+        for (range, replacement) in replacements {
+            content.replace_range(range, &replacement);
+        }
+        replace_import_aliases(&mut content, &data.imports);
+
+        sources.push(format!(
+            "// {}\n{}",
+            path.strip_prefix(&paths.root).unwrap_or(path).display(),
+            content
+        ));
+    }
+
+    // the license of the target comes first
+    licenses.sort_by_key(|(_, is_target)| !is_target);
+
+    let mut result = String::new();
+    if let Some(license) = merge_licenses(licenses, license_merging) {
+        result.push_str(&license);
+        result.push('\n');
+    }
+    for pragma in merge_pragmas(pragmas) {
+        result.push_str(&pragma);
+        result.push('\n');
+    }
+    for source in sources {
+        result.push_str("\n\n");
+        result.push_str(&source);
+    }
+
+    let collisions = definitions
+        .into_iter()
+        .filter_map(|(name, definitions)| {
+            let paths = definitions
+                .iter()
+                .filter(|(path, definition)| {
+                    definitions.iter().any(|(other_path, other)| {
+                        other_path != path && definition.collides_with(other)
+                    })
+                })
+                .map(|(path, _)| path.to_path_buf())
+                .unique()
+                .collect::<Vec<_>>();
+            (!paths.is_empty()).then(|| NameCollision { name: name.to_string(), paths })
+        })
+        .collect();
+
+    Ok(SyntacticFlattening {
+        source: format!(
+            "{}\n",
+            utils::RE_THREE_OR_MORE_NEWLINES.replace_all(&result, "\n\n").trim()
+        ),
+        collisions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_map_flattened_lines() {
+        let root = PathBuf::from("/project");
+        let lib = root.join("src/Lib.sol");
+        let target = root.join("src/Target.sol");
+        let lib_content =
+            "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\ncontract Counter {}\n";
+        let target_content = "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\nimport \"./Lib.sol\";\n\n\ncontract Target is Counter {\n    Counter c;\n}\n";
+        let flattener = Flattener {
+            targets: vec![target.clone()],
+            sources: Sources::from([
+                (lib.clone(), Source::new(lib_content)),
+                (target.clone(), Source::new(target_content)),
+            ]),
+            asts: Vec::new(),
+            ordered_sources: vec![lib.clone(), target.clone()],
+            project_root: root,
+            license_merging: LicenseMerging::default(),
+        };
+
+        let loc = |content: &str, needle: &str, nth: usize| {
+            let start = content.match_indices(needle).nth(nth).unwrap().0;
+            (start, start + needle.len())
+        };
+        let mut updates = Updates::new();
+        for (path, content, renames) in [(&lib, lib_content, 1), (&target, target_content, 2)] {
+            let updates = updates.entry(path.clone()).or_default();
+            for removed in ["// SPDX-License-Identifier: MIT", "pragma solidity ^0.8.0;"] {
+                let (start, end) = loc(content, removed, 0);
+                updates.insert((start, end, String::new()));
+            }
+            for nth in 0..renames {
+                let (start, end) = loc(content, "Counter", nth);
+                updates.insert((start, end, "Counter_0".to_string()));
+            }
+        }
+        let (start, end) = loc(target_content, "import \"./Lib.sol\";", 0);
+        updates.get_mut(&target).unwrap().insert((start, end, String::new()));
+
+        let result = FlatteningResult::new(
+            &flattener,
+            updates,
+            vec!["pragma solidity ^0.8.0;".to_string()],
+            Some("// SPDX-License-Identifier: MIT".to_string()),
+        );
+        let flattened = result.get_flattened_target();
+        assert_eq!(
+            flattened,
+            "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\n// src/Lib.sol\n\ncontract Counter_0 {}\n\n// src/Target.sol\n\ncontract Target is Counter_0 {\n    Counter_0 c;\n}\n"
+        );
+
+        // same output as joining the sources and collapsing newlines
+        let mut joined = "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n".to_string();
+        for source in &result.sources {
+            joined.push_str(&format!("\n\n{}\n{}", source.header, source.content));
+        }
+        let joined =
+            format!("{}\n", utils::RE_THREE_OR_MORE_NEWLINES.replace_all(&joined, "\n\n").trim());
+        assert_eq!(flattened, joined);
+
+        let map = result.source_map();
+        assert_eq!(
+            map.ranges(),
+            vec![
+                // the removed license identifier leaves an empty line, the empty lines that follow
+                // are collapsed
+                FlattenedLineRange { lines: 4..5, path: lib.clone(), offset: 0, line: 0 },
+                FlattenedLineRange { lines: 5..6, path: lib.clone(), offset: 57, line: 3 },
+                FlattenedLineRange { lines: 8..9, path: target.clone(), offset: 0, line: 0 },
+                FlattenedLineRange { lines: 9..12, path: target.clone(), offset: 78, line: 5 },
+            ]
+        );
+
+        // `c` of `Counter_0 c;` in the target
+        let line = flattened.lines().position(|l| l.contains("Counter_0 c;")).unwrap();
+        let column = flattened.lines().nth(line).unwrap().find(" c;").unwrap() + 1;
+        let (path, offset) = map.original_location(line, column).unwrap();
+        assert_eq!(path, target);
+        assert_eq!(&target_content[offset..offset + 2], "c;");
+        assert_eq!(map.original_location(0, 0), None);
+    }
+
+    #[test]
+    fn can_order_deps_of_multiple_targets() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = utils::canonicalized(tmp.path());
+        let src = root.join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        for (file, content) in [
+            ("Lib.sol", "library Lib {}"),
+            ("IProxy.sol", "interface IProxy {}"),
+            ("Proxy.sol", "import \"./Lib.sol\";\nimport \"./IProxy.sol\";\ncontract Proxy {}"),
+            ("Impl.sol", "import \"./Lib.sol\";\ncontract Impl {}"),
+            ("Unrelated.sol", "contract Unrelated {}"),
+        ] {
+            std::fs::write(src.join(file), content).unwrap();
+        }
+        let paths =
+            ProjectPathsConfig::builder().sources(&src).no_libs().build_with_root::<Solc>(&root);
+        let graph = Graph::resolve(&paths).unwrap();
+
+        let ordered = collect_ordered_deps_of_all(
+            &[src.join("Proxy.sol"), src.join("Impl.sol")],
+            &paths,
+            &graph,
+        )
+        .unwrap();
+        assert_eq!(
+            ordered,
+            ["IProxy.sol", "Lib.sol", "Impl.sol", "Proxy.sol"].map(|f| src.join(f)).to_vec()
+        );
+    }
+
+    #[test]
+    fn can_flatten_from_build_infos() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = utils::canonicalized(tmp.path());
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("out/build-info")).unwrap();
+        std::fs::write(root.join("src/Lib.sol"), "enum E { A }\n").unwrap();
+        std::fs::write(root.join("src/Target.sol"), "import \"./Lib.sol\";\nenum E { B }\n")
+            .unwrap();
+
+        let build_info = r#"{"output": {"sources": {
+            "src/Lib.sol": {"id": 0, "ast": {
+                "absolutePath": "src/Lib.sol", "exportedSymbols": {"E": [2]}, "id": 3,
+                "nodeType": "SourceUnit", "src": "0:13:0", "nodes": [{
+                    "canonicalName": "E", "id": 2, "name": "E", "nameLocation": "5:1:0",
+                    "nodeType": "EnumDefinition", "src": "0:12:0", "members": [{
+                        "id": 1, "name": "A", "nameLocation": "9:1:0", "nodeType": "EnumValue",
+                        "src": "9:1:0"
+                    }]
+                }]
+            }},
+            "src/Target.sol": {"id": 1, "ast": {
+                "absolutePath": "src/Target.sol", "exportedSymbols": {"E": [6]}, "id": 7,
+                "nodeType": "SourceUnit", "src": "0:33:1", "nodes": [{
+                    "absolutePath": "src/Lib.sol", "file": "./Lib.sol", "id": 4,
+                    "nameLocation": "-1:-1:-1", "nodeType": "ImportDirective", "scope": 7,
+                    "sourceUnit": 3, "src": "0:19:1", "symbolAliases": [], "unitAlias": ""
+                }, {
+                    "canonicalName": "E", "id": 6, "name": "E", "nameLocation": "25:1:1",
+                    "nodeType": "EnumDefinition", "src": "20:12:1", "members": [{
+                        "id": 5, "name": "B", "nameLocation": "29:1:1", "nodeType": "EnumValue",
+                        "src": "29:1:1"
+                    }]
+                }]
+            }}
+        }}}"#;
+        std::fs::write(root.join("out/build-info/build.json"), build_info).unwrap();
+
+        let paths = ProjectPathsConfig::builder()
+            .sources(root.join("src"))
+            .no_libs()
+            .build_with_root::<Solc>(&root);
+        let flattener = Flattener::from_build_infos(
+            &paths,
+            [root.join("out/build-info")],
+            [root.join("src/Target.sol")],
+        )
+        .unwrap();
+
+        assert_eq!(
+            flattener.flatten(),
+            "// src/Lib.sol\nenum E_0 { A }\n\n// src/Target.sol\n\nenum E_1 { B }\n"
+        );
+    }
+
+    #[test]
+    fn rejects_asts_of_different_build_infos() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = utils::canonicalized(tmp.path());
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("out/build-info")).unwrap();
+        std::fs::write(root.join("src/Lib.sol"), "enum E { A }\n").unwrap();
+        std::fs::write(root.join("src/Target.sol"), "import \"./Lib.sol\";\nenum E { B }\n")
+            .unwrap();
+
+        // two separate compilations, so the node ids of both files start at 1
+        let lib = r#"{"output": {"sources": {
+            "src/Lib.sol": {"id": 0, "ast": {
+                "absolutePath": "src/Lib.sol", "exportedSymbols": {"E": [2]}, "id": 3,
+                "nodeType": "SourceUnit", "src": "0:13:0", "nodes": [{
+                    "canonicalName": "E", "id": 2, "name": "E", "nameLocation": "5:1:0",
+                    "nodeType": "EnumDefinition", "src": "0:12:0", "members": [{
+                        "id": 1, "name": "A", "nameLocation": "9:1:0", "nodeType": "EnumValue",
+                        "src": "9:1:0"
+                    }]
+                }]
+            }}
+        }}}"#;
+        let target = r#"{"output": {"sources": {
+            "src/Target.sol": {"id": 0, "ast": {
+                "absolutePath": "src/Target.sol", "exportedSymbols": {"E": [3]}, "id": 4,
+                "nodeType": "SourceUnit", "src": "0:33:0", "nodes": [{
+                    "absolutePath": "src/Lib.sol", "file": "./Lib.sol", "id": 1,
+                    "nameLocation": "-1:-1:-1", "nodeType": "ImportDirective", "scope": 4,
+                    "sourceUnit": 3, "src": "0:19:0", "symbolAliases": [], "unitAlias": ""
+                }, {
+                    "canonicalName": "E", "id": 3, "name": "E", "nameLocation": "25:1:0",
+                    "nodeType": "EnumDefinition", "src": "20:12:0", "members": [{
+                        "id": 2, "name": "B", "nameLocation": "29:1:0", "nodeType": "EnumValue",
+                        "src": "29:1:0"
+                    }]
+                }]
+            }}
+        }}}"#;
+        std::fs::write(root.join("out/build-info/a.json"), lib).unwrap();
+        std::fs::write(root.join("out/build-info/b.json"), target).unwrap();
+
+        let paths = ProjectPathsConfig::builder()
+            .sources(root.join("src"))
+            .no_libs()
+            .build_with_root::<Solc>(&root);
+        let err = Flattener::from_build_infos(
+            &paths,
+            [root.join("out/build-info")],
+            [root.join("src/Target.sol")],
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("single compilation"), "{err}");
+
+        // the library on its own is part of a single compilation
+        let flattener = Flattener::from_build_infos(
+            &paths,
+            [root.join("out/build-info")],
+            [root.join("src/Lib.sol")],
+        )
+        .unwrap();
+        assert_eq!(flattener.flatten(), "// src/Lib.sol\nenum E { A }\n");
+    }
+
+    #[test]
+    fn can_flatten_syntactically() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = utils::canonicalized(tmp.path());
+        let src = root.join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        for (file, content) in [
+            (
+                "A.sol",
+                "// SPDX-License-Identifier: MIT OR Apache-2.0\npragma solidity ^0.8.0;\n\ncontract Shared {}\n\nenum E { A }\n\nfunction f(uint256 a) pure returns (uint256) { return a; }\n",
+            ),
+            (
+                "B.sol",
+                "// SPDX-License-Identifier: Apache-2.0\npragma solidity>=0.8.10;\npragma experimental ABIEncoderV2;\nimport {Shared as S} from \"./A.sol\";\n\nstruct E { uint256 a; }\n\ncontract B is S {}\n",
+            ),
+            (
+                "Target.sol",
+                "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\npragma abicoder v2;\nimport \"./A.sol\";\nimport \"./B.sol\";\n\ncontract Shared {}\n\nfunction f(address a) pure returns (address) { return a; }\n",
+            ),
+        ] {
+            std::fs::write(src.join(file), content).unwrap();
+        }
+
+        let paths = ProjectPathsConfig::builder().sources(&src).no_libs().build_with_root(&root);
+        let flattened =
+            flatten_syntactic(&paths, &src.join("Target.sol"), LicenseMerging::And).unwrap();
+
+        assert_eq!(
+            flattened.source,
+            "// SPDX-License-Identifier: MIT AND (MIT OR Apache-2.0) AND Apache-2.0\npragma solidity >=0.8.10 ^0.8.0;\npragma experimental ABIEncoderV2;\n\n// src/A.sol\n\ncontract Shared {}\n\nenum E { A }\n\nfunction f(uint256 a) pure returns (uint256) { return a; }\n\n// src/B.sol\n\nstruct E { uint256 a; }\n\ncontract B is Shared {}\n\n// src/Target.sol\n\ncontract Shared {}\n\nfunction f(address a) pure returns (address) { return a; }\n"
+        );
+        // the overloads of `f` don't collide
+        assert_eq!(
+            flattened.collisions,
+            vec![
+                NameCollision {
+                    name: "E".to_string(),
+                    paths: vec![src.join("A.sol"), src.join("B.sol")]
+                },
+                NameCollision {
+                    name: "Shared".to_string(),
+                    paths: vec![src.join("A.sol"), src.join("Target.sol")]
+                },
+            ]
+        );
+
+        let flattened =
+            flatten_syntactic(&paths, &src.join("Target.sol"), LicenseMerging::PerSource).unwrap();
+        assert!(flattened.source.starts_with("// SPDX-License-Identifier: MIT\n"));
+        assert!(flattened.source.contains("// src/A.sol\n// License: MIT OR Apache-2.0\n"));
+    }
+
+    #[test]
+    fn can_merge_licenses_and_pragmas() {
+        let root = PathBuf::from("/project");
+        let lib = root.join("src/Lib.sol");
+        let target = root.join("src/Target.sol");
+        let files = [
+            (
+                lib.clone(),
+                "// SPDX-License-Identifier: MIT OR Apache-2.0\npragma solidity ^0.8.0;\npragma abicoder v2;\n\ncontract Lib {}\n",
+            ),
+            (
+                target.clone(),
+                "// SPDX-License-Identifier: GPL-3.0\npragma solidity>=0.8.10;\npragma experimental SMTChecker;\n\ncontract Target {}\n",
+            ),
+        ];
+
+        // ASTs that only contain the pragma directives
+        let asts = files
+            .iter()
+            .map(|(path, content)| {
+                let mut offset = 0;
+                let mut nodes = Vec::new();
+                for line in content.split_inclusive('\n') {
+                    if line.starts_with("pragma") {
+                        let literals = line.trim_end().trim_end_matches(';').split(' ').skip(1);
+                        nodes.push(serde_json::json!({
+                            "id": nodes.len(),
+                            "literals": literals.collect::<Vec<_>>(),
+                            "nodeType": "PragmaDirective",
+                            "src": format!("{offset}:{}:0", line.trim_end().len()),
+                        }));
+                    }
+                    offset += line.len();
+                }
+                let ast = serde_json::json!({
+                    "absolutePath": path, "exportedSymbols": {}, "id": 100, "nodes": nodes,
+                    "nodeType": "SourceUnit", "src": format!("0:{}:0", content.len()),
+                });
+                (path.clone(), serde_json::from_value(ast).unwrap())
+            })
+            .collect();
+
+        let flattener = Flattener {
+            targets: vec![target.clone()],
+            sources: files
+                .iter()
+                .map(|(path, content)| (path.clone(), Source::new(*content)))
+                .collect(),
+            asts,
+            ordered_sources: vec![lib, target],
+            project_root: root,
+            license_merging: LicenseMerging::default(),
+        };
+
+        let pragmas = "pragma solidity >=0.8.10 ^0.8.0;\npragma abicoder v2;\npragma experimental SMTChecker;\n";
+        let body = "// src/Lib.sol\n\ncontract Lib {}\n\n// src/Target.sol\n\ncontract Target {}\n";
+        assert_eq!(
+            flattener.flatten(),
+            format!("// SPDX-License-Identifier: GPL-3.0\n{pragmas}\n{body}")
+        );
+
+        let flattener = flattener.with_license_merging(LicenseMerging::And);
+        assert_eq!(
+            flattener.flatten(),
+            format!(
+                "// SPDX-License-Identifier: GPL-3.0 AND (MIT OR Apache-2.0)\n{pragmas}\n{body}"
+            )
+        );
+
+        let flattener = flattener.with_license_merging(LicenseMerging::PerSource);
+        assert_eq!(
+            flattener.flatten(),
+            format!(
+                "// SPDX-License-Identifier: GPL-3.0\n{pragmas}\n// src/Lib.sol\n// License: MIT OR Apache-2.0\n\ncontract Lib {{}}\n\n// src/Target.sol\n// License: GPL-3.0\n\ncontract Target {{}}\n"
+            )
+        );
+    }
+}
@@ -15,6 +15,14 @@ pub use artifacts::{CompilerOutput, EvmVersion, SolcInput};
 
 pub mod sourcemap;
 
+pub mod cbor;
+
+pub mod debug;
+
+pub mod disasm;
+
+pub mod verify;
+
 mod artifact_output;
 pub use artifact_output::*;
 
@@ -31,7 +39,7 @@ pub use hh::{HardhatArtifact, HardhatArtifacts};
 
 pub mod resolver;
 use resolver::parse::SolData;
-pub use resolver::Graph;
+pub use resolver::{Graph, VersionSelectionPolicy};
 
 pub mod compilers;
 
@@ -42,13 +50,17 @@ pub use compile::{
 };
 
 mod config;
-pub use config::{PathStyle, ProjectPaths, ProjectPathsConfig, SolcConfig};
+pub use config::{
+    ImportExplanation, ImportResolutionStep, PathStyle, ProjectPaths, ProjectPathsConfig,
+    RemappingOutcome, SolcConfig,
+};
 
 pub mod remappings;
 
 mod filter;
 pub use filter::{
-    FileFilter, FilteredSources, SolcSparseFileFilter, SourceCompilationKind, SparseOutputFilter,
+    ContractFilter, FileFilter, FilteredSources, GlobPattern, PathFilter, RegexPattern,
+    RootedPathFilter, SolcSparseFileFilter, SourceCompilationKind, SparseOutputFilter,
     TestFileFilter,
 };
 use solang_parser::pt::SourceUnitPart;
@@ -117,6 +129,8 @@ pub struct Project<C: Compiler = Solc, T: ArtifactOutput = ConfigurableArtifacts
     solc_jobs: usize,
     /// Offline mode, if set, network access (download solc) is disallowed
     pub offline: bool,
+    /// How the compiler version is selected if multiple versions are compatible with the sources
+    pub version_policy: VersionSelectionPolicy,
     /// Windows only config value to ensure the all paths use `/` instead of `\\`, same as `solc`
     ///
     /// This is a noop on other platforms
@@ -223,6 +237,18 @@ impl<T: ArtifactOutput> Project<Solc, T> {
     pub fn flatten(&self, target: &Path) -> Result<String> {
         self.paths.flatten(target)
     }
+
+    /// Flattens the target solidity file without compiling it.
+    ///
+    /// This is a fallback for projects that don't compile. Colliding top-level names are reported
+    /// instead of renamed, see [`flatten::flatten_syntactic()`].
+    pub fn flatten_syntactic(
+        &self,
+        target: &Path,
+        license_merging: flatten::LicenseMerging,
+    ) -> Result<flatten::SyntacticFlattening> {
+        flatten::flatten_syntactic(&self.paths, target, license_merging)
+    }
 }
 
 impl<T: ArtifactOutput, C: Compiler> Project<C, T> {
@@ -569,6 +595,8 @@ pub struct ProjectBuilder<C: Compiler = Solc, T: ArtifactOutput = ConfigurableAr
     no_artifacts: bool,
     /// Use offline mode
     offline: bool,
+    /// How to select the compiler version for the sources
+    version_policy: VersionSelectionPolicy,
     /// Whether to slash paths of the `ProjectCompilerOutput`
     slash_paths: bool,
     /// handles all artifacts related tasks
@@ -591,6 +619,7 @@ impl<C: Compiler, T: ArtifactOutput> ProjectBuilder<C, T> {
             build_info: false,
             no_artifacts: false,
             offline: false,
+            version_policy: Default::default(),
             slash_paths: true,
             artifacts,
             ignored_error_codes: Vec::new(),
@@ -673,6 +702,13 @@ impl<C: Compiler, T: ArtifactOutput> ProjectBuilder<C, T> {
         self
     }
 
+    /// Sets the [`VersionSelectionPolicy`] used to select the compiler version for the sources
+    #[must_use]
+    pub fn version_policy(mut self, version_policy: VersionSelectionPolicy) -> Self {
+        self.version_policy = version_policy;
+        self
+    }
+
     /// Sets whether to slash all paths on windows
     ///
     /// If set to `true` all `\\` separators are replaced with `/`, same as solc
@@ -723,6 +759,7 @@ impl<C: Compiler, T: ArtifactOutput> ProjectBuilder<C, T> {
             compiler_severity_filter,
             solc_jobs,
             offline,
+            version_policy,
             build_info,
             slash_paths,
             ignored_file_paths,
@@ -734,6 +771,7 @@ impl<C: Compiler, T: ArtifactOutput> ProjectBuilder<C, T> {
             cached,
             no_artifacts,
             offline,
+            version_policy,
             slash_paths,
             artifacts,
             ignored_error_codes,
@@ -756,6 +794,7 @@ impl<C: Compiler, T: ArtifactOutput> ProjectBuilder<C, T> {
             compiler_severity_filter,
             solc_jobs,
             offline,
+            version_policy,
             build_info,
             slash_paths,
             settings,
@@ -782,6 +821,7 @@ impl<C: Compiler, T: ArtifactOutput> ProjectBuilder<C, T> {
                 .or_else(|| std::thread::available_parallelism().ok().map(|n| n.get()))
                 .unwrap_or(1),
             offline,
+            version_policy,
             slash_paths,
             settings: settings.unwrap_or_default(),
         })
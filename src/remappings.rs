@@ -269,6 +269,109 @@ impl Remapping {
             .collect()
     }
 
+    /// Discovers the remappings declared in the `remappings.txt` files of all dependencies in the
+    /// `lib` dir, including the dependencies of dependencies in their own `lib` dirs.
+    ///
+    /// The remappings are rebased onto the dependency's path and scoped with a `context`, relative
+    /// to `root`, so that they only apply to files inside the dependency. Because they're more
+    /// specific, they should be placed before the `root_remappings`, which are checked for
+    /// conflicts: a root remapping with the same name that applies to files of the dependency,
+    /// but points to a different target.
+    ///
+    /// The remappings are sorted by the length of their context, deepest dependency first, so
+    /// that the first matching remapping is the one solc picks, which is the one with the longest
+    /// matching context.
+    pub fn find_nested(
+        root: impl AsRef<Path>,
+        lib: impl AsRef<Path>,
+        root_remappings: &[Remapping],
+    ) -> NestedRemappings {
+        fn find(root: &Path, lib: &Path, visited: &mut HashSet<PathBuf>, out: &mut Vec<Remapping>) {
+            let Ok(entries) = std::fs::read_dir(lib) else { return };
+            let mut deps = entries
+                .filter_map(Result::ok)
+                .map(|e| e.path())
+                .filter(|p| p.is_dir())
+                .filter(|p| {
+                    !p.file_name().and_then(|n| n.to_str()).map_or(false, |n| n.starts_with('.'))
+                })
+                .collect::<Vec<_>>();
+            deps.sort();
+
+            for dep in deps {
+                // dependencies may be symlinked into multiple `lib` dirs
+                if !visited.insert(utils::canonicalized(&dep)) {
+                    continue;
+                }
+
+                let file = dep.join("remappings.txt");
+                if let Ok(content) = std::fs::read_to_string(&file) {
+                    let context = format!("{}/", dep.strip_prefix(root).unwrap_or(&dep).display());
+                    for line in content.lines().map(str::trim) {
+                        if line.is_empty() || line.starts_with('#') {
+                            continue;
+                        }
+                        match Remapping::from_str(line) {
+                            Ok(remapping) => out.push(remapping.rebase(&dep, &context)),
+                            Err(err) => warn!("{err} in \"{}\"", file.display()),
+                        }
+                    }
+                }
+
+                find(root, &dep.join(DAPPTOOLS_LIB_DIR), visited, out);
+            }
+        }
+
+        let root = root.as_ref();
+        let mut remappings = Vec::new();
+        find(root, lib.as_ref(), &mut HashSet::new(), &mut remappings);
+        remappings.sort_by_key(|r| std::cmp::Reverse(r.context.as_ref().map_or(0, String::len)));
+
+        let target = |remapping: &Remapping| {
+            let path = root.join(&remapping.path);
+            PathBuf::from(path.to_string_lossy().trim_end_matches('/'))
+        };
+        let mut conflicts = Vec::new();
+        for nested in &remappings {
+            let context = Path::new(nested.context.as_deref().unwrap_or_default());
+            for root_remapping in root_remappings {
+                let applies =
+                    root_remapping.context.as_ref().map_or(true, |ctx| context.starts_with(ctx));
+                if applies
+                    && root_remapping.name == nested.name
+                    && target(root_remapping) != target(nested)
+                {
+                    conflicts.push(RemappingConflict {
+                        root: root_remapping.clone(),
+                        nested: nested.clone(),
+                    });
+                }
+            }
+        }
+
+        NestedRemappings { remappings, conflicts }
+    }
+
+    /// Rebases a remapping declared by the dependency at `dir` onto the dependency's path, scoped
+    /// to the `context` of the dependency.
+    fn rebase(self, dir: &Path, context: &str) -> Self {
+        let path = if Path::new(&self.path).is_absolute() {
+            self.path
+        } else {
+            let rebased = dir.join(&self.path).display().to_string();
+            if self.path.ends_with('/') && !rebased.ends_with('/') {
+                format!("{rebased}/")
+            } else {
+                rebased
+            }
+        };
+        let context = match self.context {
+            Some(ctx) => format!("{context}{}", ctx.trim_start_matches("./")),
+            None => context.to_string(),
+        };
+        Remapping { context: Some(context), name: self.name, path }
+    }
+
     /// Converts any `\\` separators in the `path` to `/`
     pub fn slash_path(&mut self) {
         #[cfg(windows)]
@@ -282,6 +385,31 @@ impl Remapping {
     }
 }
 
+/// The remappings declared by nested dependencies, see [`Remapping::find_nested()`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NestedRemappings {
+    /// The rebased remappings, scoped to their dependency
+    pub remappings: Vec<Remapping>,
+    /// Root remappings that conflict with remappings of a dependency
+    pub conflicts: Vec<RemappingConflict>,
+}
+
+/// A root remapping that applies to the files of a dependency, but points to a different target
+/// than the dependency's own remapping with the same name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemappingConflict {
+    /// The remapping of the project
+    pub root: Remapping,
+    /// The rebased remapping of the dependency
+    pub nested: Remapping,
+}
+
+impl fmt::Display for RemappingConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "remapping {} conflicts with {} of the dependency", self.root, self.nested)
+    }
+}
+
 /// A relative [`Remapping`] that's aware of the current location
 ///
 /// See [`RelativeRemappingPathBuf`]
@@ -812,7 +940,7 @@ fn last_nested_source_dir(root: &Path, dir: &Path) -> PathBuf {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{utils::tempdir, ProjectPathsConfig};
+    use crate::{utils::tempdir, ProjectPathsConfig, Solc};
 
     #[test]
     fn relative_remapping() {
@@ -880,6 +1008,74 @@ mod tests {
         format!("{}/", p.display())
     }
 
+    #[test]
+    #[cfg_attr(windows, ignore = "Windows remappings #2347")]
+    fn find_nested_remappings() {
+        let tmp_dir = tempdir("root").unwrap();
+        let root = tmp_dir.path();
+        let lib = root.join("lib");
+        mkdir_or_touch(
+            root,
+            &["lib/dep/lib/ds-test/src/test.sol", "lib/dep/lib/solmate/lib/ds-test/src/test.sol"],
+        );
+        std::fs::write(
+            lib.join("dep/remappings.txt"),
+            "# comment\nds-test/=lib/ds-test/src/\nsolmate/=lib/solmate/src/\nsrc/:utils/=src/utils/\n",
+        )
+        .unwrap();
+        std::fs::write(lib.join("dep/lib/solmate/remappings.txt"), "ds-test/=lib/ds-test/src/")
+            .unwrap();
+
+        let root_remappings = [
+            Remapping::from_str("ds-test/=lib/ds-test/src/").unwrap(),
+            Remapping::from_str("solmate/=lib/dep/lib/solmate/src/").unwrap(),
+        ];
+        let nested = Remapping::find_nested(root, &lib, &root_remappings);
+
+        let dep = lib.join("dep");
+        let solmate = dep.join("lib/solmate");
+        let remapping = |context: &str, name: &str, path: PathBuf| Remapping {
+            context: Some(context.to_string()),
+            name: name.to_string(),
+            path: to_str(path),
+        };
+        assert_eq!(
+            nested.remappings,
+            vec![
+                remapping("lib/dep/lib/solmate/", "ds-test/", solmate.join("lib/ds-test/src")),
+                remapping("lib/dep/src/", "utils/", dep.join("src/utils")),
+                remapping("lib/dep/", "ds-test/", dep.join("lib/ds-test/src")),
+                remapping("lib/dep/", "solmate/", dep.join("lib/solmate/src")),
+            ]
+        );
+        assert_eq!(
+            nested.conflicts,
+            vec![
+                RemappingConflict {
+                    root: root_remappings[0].clone(),
+                    nested: nested.remappings[0].clone()
+                },
+                RemappingConflict {
+                    root: root_remappings[0].clone(),
+                    nested: nested.remappings[2].clone()
+                },
+            ]
+        );
+
+        // files of the nested dependency resolve through its own remappings, like solc does
+        let paths = ProjectPathsConfig::builder()
+            .remappings(nested.remappings.into_iter().chain(root_remappings))
+            .build_with_root::<Solc>(root);
+        assert_eq!(
+            paths.resolve_library_import(&solmate.join("src"), Path::new("ds-test/test.sol")),
+            Some(solmate.join("lib/ds-test/src/test.sol"))
+        );
+        assert_eq!(
+            paths.resolve_library_import(&dep.join("src"), Path::new("ds-test/test.sol")),
+            Some(dep.join("lib/ds-test/src/test.sol"))
+        );
+    }
+
     #[test]
     #[cfg_attr(windows, ignore = "Windows remappings #2347")]
     fn find_remapping_dapptools() {
@@ -0,0 +1,177 @@
+//! Explanations for version conflicts in the dependency graph.
+
+use crate::{compilers::ParsedSource, utils, Graph};
+use semver::{Version, VersionReq};
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    fmt,
+    path::PathBuf,
+};
+
+/// A `pragma solidity` requirement of a file in the dependency tree of a conflicting input file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictingRequirement {
+    /// The file that declares the requirement, relative to the project root
+    pub file: PathBuf,
+    /// The version requirement of the file
+    pub requirement: VersionReq,
+    /// The shortest import chain from the input file to `file`, both inclusive
+    pub import_chain: Vec<PathBuf>,
+}
+
+impl fmt::Display for ConflictingRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.file.display(), self.requirement)?;
+        if self.import_chain.len() > 1 {
+            write!(f, " (imported via ")?;
+            for (i, file) in self.import_chain.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " -> ")?;
+                }
+                write!(f, "{}", file.display())?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+/// Explains why there's no compiler version that satisfies an input file and all its imports.
+///
+/// See [`Graph::explain_version_conflict()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionConflict {
+    /// The input file, relative to the project root
+    pub file: PathBuf,
+    /// All version requirements in the dependency tree of `file`, including its own
+    pub requirements: Vec<ConflictingRequirement>,
+    /// Indices into `requirements` of two requirements that can't be satisfied together.
+    ///
+    /// This is `None` if no two requirements are disjoint, because a requirement doesn't match any
+    /// of the available versions, or only three or more requirements have no version in common.
+    pub conflict: Option<(usize, usize)>,
+    /// Indices into `requirements` of the minimal set of requirements that need to be relaxed so
+    /// that `suggested_version` satisfies the entire tree.
+    pub relax: Vec<usize>,
+    /// The version that satisfies the most requirements
+    pub suggested_version: Option<Version>,
+}
+
+impl fmt::Display for VersionConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((a, b)) = self.conflict {
+            write!(
+                f,
+                "{} can't be compiled, these requirements have no version in common:\n    {}\n    {}",
+                self.file.display(),
+                self.requirements[a],
+                self.requirements[b]
+            )?;
+        } else {
+            write!(f, "{} can't be compiled, no version satisfies:", self.file.display())?;
+            for req in &self.requirements {
+                write!(f, "\n    {req}")?;
+            }
+        }
+        if let Some(version) = &self.suggested_version {
+            write!(f, "\nRelax the following pragmas to compile with {version}:")?;
+            for idx in &self.relax {
+                let req = &self.requirements[*idx];
+                write!(f, "\n    {} {}", req.file.display(), req.requirement)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<D: ParsedSource> Graph<D> {
+    /// Explains why no version of `versions` satisfies the input file `idx` and all its imports.
+    ///
+    /// The requirements are compared by the sets of `versions` they match, which aren't
+    /// necessarily contiguous, for example because pre-releases are only matched by requirements
+    /// that mention them. The suggested version is the one that satisfies the most requirements,
+    /// preferring the highest version.
+    ///
+    /// Returns `None` if there's a version that satisfies all requirements.
+    pub fn explain_version_conflict(
+        &self,
+        idx: usize,
+        versions: &[Version],
+    ) -> Option<VersionConflict> {
+        let mut versions = versions.to_vec();
+        versions.sort_unstable();
+        versions.dedup();
+
+        // shortest import chain to every node of the dependency tree
+        let mut parents = HashMap::from([(idx, idx)]);
+        let mut queue = VecDeque::from([idx]);
+        let mut tree = Vec::new();
+        while let Some(node) = queue.pop_front() {
+            tree.push(node);
+            for dep in self.imported_nodes(node) {
+                if !parents.contains_key(dep) {
+                    parents.insert(*dep, node);
+                    queue.push_back(*dep);
+                }
+            }
+        }
+
+        let source_name =
+            |node: usize| utils::source_name(&self.node(node).path, &self.root).into();
+        let mut requirements = Vec::new();
+        // indices of the `versions` matched by every requirement
+        let mut matches: Vec<BTreeSet<usize>> = Vec::new();
+        for node in tree {
+            let Some(req) = self.node(node).data.version_req() else { continue };
+            let mut import_chain = vec![source_name(node)];
+            let mut current = node;
+            while current != idx {
+                current = parents[&current];
+                import_chain.push(source_name(current));
+            }
+            import_chain.reverse();
+
+            matches.push((0..versions.len()).filter(|v| req.matches(&versions[*v])).collect());
+            requirements.push(ConflictingRequirement {
+                file: source_name(node),
+                requirement: req.clone(),
+                import_chain,
+            });
+        }
+        if requirements.is_empty() {
+            return None;
+        }
+
+        // the version that is matched by the most requirements
+        let best = (0..versions.len())
+            .map(|v| (matches.iter().filter(|matched| matched.contains(&v)).count(), v))
+            .max()
+            .filter(|(count, _)| *count > 0);
+        if best.map_or(false, |(count, _)| count == requirements.len()) {
+            return None;
+        }
+        let relax = matches
+            .iter()
+            .enumerate()
+            .filter(|(_, matched)| best.map_or(true, |(_, v)| !matched.contains(&v)))
+            .map(|(i, _)| i)
+            .collect();
+
+        // two requirements that match versions, but none in common
+        let conflict = (0..matches.len())
+            .flat_map(|a| (a + 1..matches.len()).map(move |b| (a, b)))
+            .find(|(a, b)| {
+                !matches[*a].is_empty()
+                    && !matches[*b].is_empty()
+                    && matches[*a].is_disjoint(&matches[*b])
+            });
+
+        Some(VersionConflict {
+            file: source_name(idx),
+            requirements,
+            conflict,
+            relax,
+            suggested_version: best.map(|(_, v)| versions[v].clone()),
+        })
+    }
+}
@@ -0,0 +1,157 @@
+//! Export of the dependency graph to Graphviz DOT, Mermaid and JSON.
+
+use crate::{compilers::ParsedSource, utils, Graph};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap},
+    io,
+    io::Write,
+    path::PathBuf,
+    str::FromStr,
+};
+
+/// The supported output formats of [`ExportedGraph::write`].
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum ExportFormat {
+    /// Graphviz DOT
+    #[default]
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+    /// JSON adjacency list, see [`ExportedGraph`]
+    Json,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(ExportFormat::Dot),
+            "mermaid" => Ok(ExportFormat::Mermaid),
+            "json" => Ok(ExportFormat::Json),
+            s => Err(format!("invalid export format: {s}")),
+        }
+    }
+}
+
+/// A single file of the [`ExportedGraph`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedNode {
+    /// The id of the node in the graph
+    pub id: usize,
+    /// The path of the file, relative to the project root
+    pub path: PathBuf,
+    /// The `pragma solidity` version requirement of the file
+    pub version_requirement: Option<String>,
+    /// Whether the file is a library file, i.e. it was not an input file but added as an import
+    pub is_library: bool,
+    /// All compiler versions the file is compiled with
+    pub compiler_versions: Vec<Version>,
+    /// The ids of all nodes this file imports
+    pub imports: Vec<usize>,
+}
+
+/// Serializable representation of a [`Graph`] that can be written in any [`ExportFormat`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedGraph {
+    pub nodes: Vec<ExportedNode>,
+}
+
+impl ExportedGraph {
+    /// Creates the export of the given graph, without resolved compiler versions.
+    pub fn new<D: ParsedSource>(graph: &Graph<D>) -> Self {
+        let nodes = graph
+            .edges
+            .files()
+            .map(|id| {
+                let node = graph.node(id);
+                ExportedNode {
+                    id,
+                    path: utils::source_name(&node.path, &graph.root).to_path_buf(),
+                    version_requirement: node.data.version_req().map(|req| req.to_string()),
+                    is_library: id >= graph.edges.num_source_files(),
+                    compiler_versions: Vec::new(),
+                    imports: graph.imported_nodes(id).to_vec(),
+                }
+            })
+            .collect();
+        Self { nodes }
+    }
+
+    /// Sets the resolved compiler versions of the nodes, see [`Graph::node_compiler_versions`].
+    pub fn with_compiler_versions(mut self, versions: &HashMap<usize, BTreeSet<Version>>) -> Self {
+        for node in self.nodes.iter_mut() {
+            node.compiler_versions =
+                versions.get(&node.id).map(|v| v.iter().cloned().collect()).unwrap_or_default();
+        }
+        self
+    }
+
+    /// Writes the graph in the given format.
+    pub fn write(&self, format: ExportFormat, out: &mut dyn Write) -> io::Result<()> {
+        match format {
+            ExportFormat::Dot => self.write_dot(out),
+            ExportFormat::Mermaid => self.write_mermaid(out),
+            ExportFormat::Json => {
+                serde_json::to_writer_pretty(&mut *out, self)?;
+                writeln!(out)
+            }
+        }
+    }
+
+    fn write_dot(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "digraph {{")?;
+        for node in &self.nodes {
+            let label = node_label(node, "\\n", |s| s.replace('\\', "\\\\").replace('"', "\\\""));
+            let style = if node.is_library { ", style=dashed" } else { "" };
+            writeln!(out, "    {} [label=\"{label}\"{style}];", node.id)?;
+        }
+        for node in &self.nodes {
+            for import in &node.imports {
+                writeln!(out, "    {} -> {import};", node.id)?;
+            }
+        }
+        writeln!(out, "}}")
+    }
+
+    fn write_mermaid(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "graph TD")?;
+        for node in &self.nodes {
+            let label = node_label(node, "<br/>", |s| s.replace('"', "#quot;"));
+            writeln!(out, "    n{}[\"{label}\"]", node.id)?;
+        }
+        for node in &self.nodes {
+            for import in &node.imports {
+                writeln!(out, "    n{} --> n{import}", node.id)?;
+            }
+        }
+        let libraries = self.nodes.iter().filter(|n| n.is_library).collect::<Vec<_>>();
+        if !libraries.is_empty() {
+            writeln!(out, "    classDef library stroke-dasharray: 5 5")?;
+            for node in libraries {
+                writeln!(out, "    class n{} library", node.id)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the label of the node: its path, version requirement and compiler versions, each on a
+/// separate line.
+fn node_label(node: &ExportedNode, newline: &str, escape: impl Fn(&str) -> String) -> String {
+    let mut label = escape(&node.path.display().to_string());
+    if let Some(req) = &node.version_requirement {
+        label.push_str(newline);
+        label.push_str(&escape(req));
+    }
+    if !node.compiler_versions.is_empty() {
+        label.push_str(newline);
+        label.push_str(
+            &node.compiler_versions.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "),
+        );
+    }
+    label
+}
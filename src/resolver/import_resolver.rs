@@ -0,0 +1,124 @@
+//! Pluggable resolution of import paths.
+
+use crate::{error::Result, remappings::Remapping};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Resolves import paths to files on disk.
+///
+/// Custom resolvers are consulted by the [`Graph`](crate::Graph) builder before the built-in
+/// resolution of [`ProjectPathsConfig`](crate::ProjectPathsConfig), which applies remappings,
+/// checks the library dirs and include paths, and falls back to `node_modules`. This allows
+/// resolving custom schemes like `pkg:` or a local package registry mirror.
+///
+/// Only non-relative imports are passed to custom resolvers, because solc always resolves relative
+/// imports relative to the importing file.
+///
+/// Since solc doesn't know about custom schemes, the graph records a remapping from the directory of
+/// every import resolved by a custom resolver to the directory of the resolved file, see
+/// [`GraphEdges::import_remappings()`](crate::resolver::GraphEdges::import_remappings), which is
+/// passed to solc together with the configured remappings.
+pub trait ImportResolver: Send + Sync {
+    /// Attempts to resolve the `import` of a file located in the `cwd` directory.
+    ///
+    /// Returns `Ok(None)` if the import is not handled by this resolver, in which case the next
+    /// resolver is consulted.
+    fn resolve_import(&self, cwd: &Path, import: &Path) -> Result<Option<PathBuf>>;
+}
+
+impl<F> ImportResolver for F
+where
+    F: Fn(&Path, &Path) -> Result<Option<PathBuf>> + Send + Sync,
+{
+    fn resolve_import(&self, cwd: &Path, import: &Path) -> Result<Option<PathBuf>> {
+        self(cwd, import)
+    }
+}
+
+/// A chain of [`ImportResolver`]s that are consulted in order.
+///
+/// The first resolver that resolves an import wins, errors are returned immediately.
+#[derive(Clone, Default)]
+pub struct ImportResolverChain {
+    resolvers: Vec<Arc<dyn ImportResolver>>,
+}
+
+impl ImportResolverChain {
+    /// Creates an empty chain
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends the resolver to the chain
+    #[must_use]
+    pub fn with(mut self, resolver: impl ImportResolver + 'static) -> Self {
+        self.push(resolver);
+        self
+    }
+
+    /// Appends the resolver to the chain
+    pub fn push(&mut self, resolver: impl ImportResolver + 'static) {
+        self.resolvers.push(Arc::new(resolver));
+    }
+
+    /// Returns the number of resolvers in the chain
+    pub fn len(&self) -> usize {
+        self.resolvers.len()
+    }
+
+    /// Returns `true` if the chain doesn't contain any resolvers
+    pub fn is_empty(&self) -> bool {
+        self.resolvers.is_empty()
+    }
+}
+
+impl ImportResolver for ImportResolverChain {
+    fn resolve_import(&self, cwd: &Path, import: &Path) -> Result<Option<PathBuf>> {
+        for resolver in &self.resolvers {
+            if let Some(resolved) = resolver.resolve_import(cwd, import)? {
+                return Ok(Some(resolved));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Returns the remapping that makes solc resolve `import` of the `context` file to the source unit
+/// name `resolved`.
+///
+/// Remappings always map directories, so this maps the leading components of `import` that differ
+/// from `resolved`. Returns `None` if the file names differ or the import has no directory.
+pub(crate) fn import_remapping(
+    context: &Path,
+    import: &Path,
+    resolved: &Path,
+) -> Option<Remapping> {
+    let mut import_dir = import.components().collect::<Vec<_>>();
+    let mut resolved_dir = resolved.components().collect::<Vec<_>>();
+    if import_dir.len() < 2 || import_dir.last() != resolved_dir.last() {
+        return None;
+    }
+    import_dir.pop();
+    resolved_dir.pop();
+    while import_dir.len() > 1 && resolved_dir.len() > 1 && import_dir.last() == resolved_dir.last()
+    {
+        import_dir.pop();
+        resolved_dir.pop();
+    }
+    let dir =
+        |components: Vec<_>| format!("{}/", components.into_iter().collect::<PathBuf>().display());
+    Some(Remapping {
+        context: Some(context.display().to_string()),
+        name: dir(import_dir),
+        path: dir(resolved_dir),
+    })
+}
+
+impl fmt::Debug for ImportResolverChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImportResolverChain").field("resolvers", &self.resolvers.len()).finish()
+    }
+}
@@ -0,0 +1,194 @@
+//! Detection of unused, shadowed and broken remappings.
+
+use crate::{
+    compilers::ParsedSource,
+    config::{apply_remapping, remapping_applies_to},
+    remappings::Remapping,
+    Graph, ProjectPathsConfig,
+};
+use std::{
+    collections::HashSet,
+    fmt,
+    path::{Component, Path},
+};
+
+/// A problem with a remapping, see [`Graph::lint_remappings()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemappingIssue {
+    /// No import of the graph is resolved via the remapping
+    Unused(Remapping),
+    /// The remapping has the same context and prefix as an earlier remapping, so it is never used.
+    ///
+    /// This is a conflict if the targets differ.
+    Duplicate { remapping: Remapping, first: Remapping },
+    /// The prefix of the remapping starts with the prefix of an earlier remapping that applies to
+    /// the same files, so the earlier remapping always takes precedence, while solc would prefer
+    /// the longer prefix.
+    Shadowed { remapping: Remapping, by: Remapping },
+    /// The context of the remapping doesn't match any file of the graph
+    UnmatchedContext(Remapping),
+    /// The target of the remapping doesn't exist
+    MissingTarget(Remapping),
+}
+
+impl RemappingIssue {
+    /// Returns the remapping the issue is about
+    pub fn remapping(&self) -> &Remapping {
+        match self {
+            Self::Unused(remapping)
+            | Self::Duplicate { remapping, .. }
+            | Self::Shadowed { remapping, .. }
+            | Self::UnmatchedContext(remapping)
+            | Self::MissingTarget(remapping) => remapping,
+        }
+    }
+}
+
+impl fmt::Display for RemappingIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unused(remapping) => write!(f, "remapping {remapping} is never used"),
+            Self::Duplicate { remapping, first } if remapping.path != first.path => {
+                write!(f, "remapping {remapping} conflicts with {first}")
+            }
+            Self::Duplicate { remapping, first } => {
+                write!(f, "remapping {remapping} is a duplicate of {first}")
+            }
+            Self::Shadowed { remapping, by } => {
+                write!(f, "remapping {remapping} is shadowed by {by}")
+            }
+            Self::UnmatchedContext(remapping) => {
+                write!(f, "context of remapping {remapping} doesn't match any file")
+            }
+            Self::MissingTarget(remapping) => {
+                write!(f, "target of remapping {remapping} doesn't exist")
+            }
+        }
+    }
+}
+
+impl<D: ParsedSource> Graph<D> {
+    /// Checks the remappings of `paths` against the imports of this graph.
+    ///
+    /// Like [`ProjectPathsConfig::resolve_library_import()`], the first remapping that applies to
+    /// an import is the one that's used, so remappings that are shadowed by or duplicate an earlier
+    /// remapping are reported as such and not as unused.
+    pub fn lint_remappings<C>(&self, paths: &ProjectPathsConfig<C>) -> Vec<RemappingIssue> {
+        let remappings = &paths.remappings;
+
+        // directories of all files in the graph, relative to the root
+        let dirs = self
+            .nodes
+            .iter()
+            .filter_map(|node| node.path.parent())
+            .map(|dir| dir.strip_prefix(&self.root).unwrap_or(dir))
+            .collect::<HashSet<_>>();
+
+        let mut used = HashSet::new();
+        for node in &self.nodes {
+            let Some(dir) = node.path.parent() else { continue };
+            let dir = dir.strip_prefix(&self.root).unwrap_or(dir);
+            for import in node.data.resolve_imports(paths).unwrap_or_default() {
+                if matches!(
+                    import.components().next(),
+                    Some(Component::CurDir | Component::ParentDir)
+                ) {
+                    continue;
+                }
+                if let Some(idx) = remappings.iter().position(|r| {
+                    remapping_applies_to(r, dir) && apply_remapping(r, &import).is_some()
+                }) {
+                    used.insert(idx);
+                }
+            }
+        }
+
+        let mut issues = Vec::new();
+        for (idx, remapping) in remappings.iter().enumerate() {
+            let earlier = remappings[..idx].iter().find(|other| {
+                let applies = match (&other.context, &remapping.context) {
+                    (None, _) => true,
+                    (Some(other), Some(ctx)) => Path::new(ctx).starts_with(other),
+                    (Some(_), None) => false,
+                };
+                applies && Path::new(&remapping.name).starts_with(&other.name)
+            });
+            let matches_files = remapping
+                .context
+                .as_ref()
+                .map_or(true, |ctx| dirs.iter().any(|dir| dir.starts_with(ctx)));
+
+            if let Some(other) = earlier {
+                let (remapping, other) = (remapping.clone(), other.clone());
+                issues.push(
+                    if remapping.name == other.name && remapping.context == other.context {
+                        RemappingIssue::Duplicate { remapping, first: other }
+                    } else {
+                        RemappingIssue::Shadowed { remapping, by: other }
+                    },
+                );
+            } else if !matches_files {
+                issues.push(RemappingIssue::UnmatchedContext(remapping.clone()));
+            } else if !used.contains(&idx) {
+                issues.push(RemappingIssue::Unused(remapping.clone()));
+            }
+
+            if !paths.root.join(&remapping.path).exists() {
+                issues.push(RemappingIssue::MissingTarget(remapping.clone()));
+            }
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{resolver::parse::SolData, utils, Solc};
+    use std::fs;
+
+    #[test]
+    fn can_lint_remappings() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = utils::canonicalized(tmp.path());
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("lib/dep/sub")).unwrap();
+        fs::write(
+            root.join("src/A.sol"),
+            "import \"dep/Dep.sol\";\nimport \"dep/sub/Sub.sol\";\nimport \"./B.sol\";",
+        )
+        .unwrap();
+        fs::write(root.join("src/B.sol"), "contract B {}").unwrap();
+        fs::write(root.join("lib/dep/Dep.sol"), "contract Dep {}").unwrap();
+        fs::write(root.join("lib/dep/sub/Sub.sol"), "contract Sub {}").unwrap();
+
+        let remappings = [
+            "dep/=lib/dep/",
+            "dep/sub/=lib/dep/sub/",
+            "dep/=lib/dep2/",
+            "unused/=lib/dep/",
+            "test/:dep/=lib/dep/",
+            "test/:other/=lib/dep/",
+        ]
+        .map(|r| r.parse::<Remapping>().unwrap());
+        let paths = ProjectPathsConfig::builder()
+            .sources(root.join("src"))
+            .no_libs()
+            .remappings(remappings.clone())
+            .build_with_root::<Solc>(&root);
+        let graph = Graph::<SolData>::resolve(&paths).unwrap();
+
+        let [dep, sub, dep2, unused, test, other] = remappings;
+        assert_eq!(
+            graph.lint_remappings(&paths),
+            vec![
+                RemappingIssue::Shadowed { remapping: sub, by: dep.clone() },
+                RemappingIssue::Duplicate { remapping: dep2.clone(), first: dep.clone() },
+                RemappingIssue::MissingTarget(dep2),
+                RemappingIssue::Unused(unused),
+                RemappingIssue::Shadowed { remapping: test, by: dep },
+                RemappingIssue::UnmatchedContext(other),
+            ]
+        );
+    }
+}
@@ -34,6 +34,9 @@
 //! difficult problem, because what would be the "best" solution. In this case, just choose the
 //! latest (installed) Solc version and try to minimize the number of Solc versions used.
 //!
+//! Which of the compatible versions is selected can be configured with a
+//! [`VersionSelectionPolicy`].
+//!
 //! ## Performance
 //!
 //! Note that this is a relatively performance-critical portion of the ethers-solc preprocessing.
@@ -49,23 +52,37 @@
 use crate::{
     compilers::{Compiler, CompilerVersion, CompilerVersionManager, ParsedSource},
     error::Result,
+    remappings::Remapping,
     utils, ProjectPathsConfig, SolcError, Source, Sources,
 };
-use core::fmt;
-use parse::SolData;
+use core::fmt::{self, Write};
+use parse::{SolContract, SolData};
 use rayon::prelude::*;
 use semver::VersionReq;
 use std::{
-    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
     io,
     path::{Path, PathBuf},
 };
 
+mod conflict;
+mod export;
+mod import_resolver;
+mod lint;
+mod node_modules;
 pub mod parse;
+mod policy;
 mod tree;
 
 use crate::utils::find_case_sensitive_existing_file;
+pub use conflict::{ConflictingRequirement, VersionConflict};
+pub use export::{ExportFormat, ExportedGraph, ExportedNode};
+use import_resolver::import_remapping;
+pub use import_resolver::{ImportResolver, ImportResolverChain};
+pub use lint::RemappingIssue;
+pub use node_modules::NodeModulesResolver;
 pub use parse::SolImportAlias;
+pub use policy::VersionSelectionPolicy;
 pub use tree::{print, Charset, TreeOptions};
 
 /// The underlying edges of the graph which only contains the raw relationship data.
@@ -101,6 +118,11 @@ pub struct GraphEdges<D> {
     /// nice with the standard-json import format, since the VFS won't be able to resolve
     /// "src/Contract.sol" without help via `--include-path`
     resolved_solc_include_paths: BTreeSet<PathBuf>,
+    /// remappings for all imports resolved by a custom [`ImportResolver`]
+    ///
+    /// solc doesn't know how these imports were resolved, so the directory of each one is remapped
+    /// to the directory of the resolved file, in the context of the importing file
+    import_remappings: BTreeSet<Remapping>,
 }
 
 impl<D> GraphEdges<D> {
@@ -129,6 +151,11 @@ impl<D> GraphEdges<D> {
         &self.resolved_solc_include_paths
     }
 
+    /// Returns the remappings for all imports resolved by a custom [`ImportResolver`]
+    pub fn import_remappings(&self) -> &BTreeSet<Remapping> {
+        &self.import_remappings
+    }
+
     /// Returns all imports that we failed to resolve
     pub fn unresolved_imports(&self) -> &HashSet<(PathBuf, PathBuf)> {
         &self.unresolved_imports
@@ -162,6 +189,38 @@ impl<D> GraphEdges<D> {
         }
     }
 
+    /// Returns all files that directly or transitively import the given file
+    pub fn all_importers(&self, file: impl AsRef<Path>) -> HashSet<&PathBuf> {
+        self.affected_nodes([file])
+            .filter(|(_, is_changed)| !is_changed)
+            .map(|(idx, _)| &self.rev_indices[&idx])
+            .collect()
+    }
+
+    /// Returns the ids of the given files and all files that directly or transitively import
+    /// them, paired with whether the id is one of the given files.
+    ///
+    /// Files that are not part of the graph are ignored.
+    fn affected_nodes<P: AsRef<Path>>(
+        &self,
+        files: impl IntoIterator<Item = P>,
+    ) -> impl Iterator<Item = (usize, bool)> {
+        let changed = files
+            .into_iter()
+            .filter_map(|file| self.indices.get(file.as_ref()).copied())
+            .collect::<HashSet<_>>();
+        let mut visited = changed.clone();
+        let mut stack = changed.iter().copied().collect::<Vec<_>>();
+        while let Some(idx) = stack.pop() {
+            for importer in &self.rev_edges[idx] {
+                if visited.insert(*importer) {
+                    stack.push(*importer);
+                }
+            }
+        }
+        visited.into_iter().map(move |idx| (idx, changed.contains(&idx)))
+    }
+
     /// Returns the id of the given file
     pub fn node_id(&self, file: impl AsRef<Path>) -> usize {
         self.indices[file.as_ref()]
@@ -196,6 +255,29 @@ impl<D> GraphEdges<D> {
     }
 }
 
+impl<D: ParsedSource> GraphEdges<D> {
+    /// Returns the given files and all files that directly or transitively import them, mapped to
+    /// the names of the contracts they define.
+    ///
+    /// This can be used to determine which sources and contracts are affected by a change to the
+    /// given files. Files that are not part of the graph are ignored.
+    ///
+    /// Note: the contract names are only available once the graph was split, see
+    /// [`Graph::into_sources()`], use [`Graph::affected_contracts()`] otherwise.
+    pub fn affected_contracts<P: AsRef<Path>>(
+        &self,
+        changed: impl IntoIterator<Item = P>,
+    ) -> BTreeMap<PathBuf, Vec<String>> {
+        self.affected_nodes(changed)
+            .map(|(idx, _)| {
+                let names =
+                    self.data.get(&idx).map(|data| data.contract_names()).unwrap_or_default();
+                (self.rev_indices[&idx].clone(), names)
+            })
+            .collect()
+    }
+}
+
 /// Represents a fully-resolved solidity dependency graph. Each node in the graph
 /// is a file and edges represent dependencies between them.
 /// See also <https://docs.soliditylang.org/en/latest/layout-of-source-files.html?highlight=import#importing-other-source-files>
@@ -222,6 +304,13 @@ impl<D: ParsedSource> Graph<D> {
         tree::print(self, &opts, &mut out).expect("failed to write to stdout.")
     }
 
+    /// Returns the graph as [`ExportedGraph`] which can be written as DOT, Mermaid or JSON.
+    ///
+    /// See also [`Self::node_compiler_versions()`] to include the resolved compiler versions.
+    pub fn export(&self) -> ExportedGraph {
+        ExportedGraph::new(self)
+    }
+
     /// Returns a list of nodes the given node index points to for the given kind.
     pub fn imported_nodes(&self, from: usize) -> &[usize] {
         self.edges.imported_nodes(from)
@@ -303,6 +392,29 @@ impl<D: ParsedSource> Graph<D> {
         self.edges.imports(path)
     }
 
+    /// Returns all files that directly or transitively import the given file
+    pub fn all_importers(&self, path: impl AsRef<Path>) -> HashSet<&PathBuf> {
+        self.edges.all_importers(path)
+    }
+
+    /// Returns the given files and all files that directly or transitively import them, mapped to
+    /// the names of the contracts they define.
+    ///
+    /// This can be used to determine which sources and contracts are affected by a change to the
+    /// given files. Files that are not part of the graph are ignored.
+    pub fn affected_contracts<P: AsRef<Path>>(
+        &self,
+        changed: impl IntoIterator<Item = P>,
+    ) -> BTreeMap<PathBuf, Vec<String>> {
+        self.edges
+            .affected_nodes(changed)
+            .map(|(idx, _)| {
+                let node = self.node(idx);
+                (node.path.clone(), node.data.contract_names())
+            })
+            .collect()
+    }
+
     /// Resolves a number of sources within the given config
     pub fn resolve_sources<C: Compiler<ParsedSource = D>>(
         paths: &ProjectPathsConfig<C>,
@@ -330,6 +442,19 @@ impl<D: ParsedSource> Graph<D> {
             Ok(())
         }
 
+        /// consults the custom import resolvers for non-relative imports, since relative imports
+        /// are always resolved relative to the importing file by solc
+        fn resolve_custom_import<C>(
+            paths: &ProjectPathsConfig<C>,
+            cwd: &Path,
+            import: &Path,
+        ) -> Result<Option<PathBuf>> {
+            if !paths.consults_import_resolvers(import) {
+                return Ok(None);
+            }
+            paths.import_resolvers.resolve_import(cwd, import)
+        }
+
         // we start off by reading all input files, which includes all solidity files from the
         // source and test folder
         let mut unresolved: VecDeque<_> = sources
@@ -355,6 +480,9 @@ impl<D: ParsedSource> Graph<D> {
         // that use absolute imports like `import "src/Contract.sol"`
         let mut resolved_solc_include_paths = BTreeSet::new();
 
+        // remappings for all imports resolved by a custom resolver
+        let mut import_remappings = BTreeSet::new();
+
         // keep track of all unique paths that we failed to resolve to not spam the reporter with
         // the same path
         let mut unresolved_imports = HashSet::new();
@@ -370,11 +498,32 @@ impl<D: ParsedSource> Graph<D> {
             };
 
             for import_path in node.data.resolve_imports(paths)? {
-                match paths.resolve_import_and_include_paths(
-                    cwd,
-                    &import_path,
-                    &mut resolved_solc_include_paths,
-                ) {
+                let resolved = match resolve_custom_import(paths, cwd, &import_path) {
+                    Ok(Some(import)) => {
+                        if let Some(remapping) = import_remapping(
+                            utils::source_name(&path, &paths.root),
+                            &import_path,
+                            utils::source_name(&import, &paths.root),
+                        ) {
+                            import_remappings.insert(remapping);
+                        } else {
+                            warn!(
+                                "can't remap import \"{}\" of {} to {} for solc",
+                                import_path.display(),
+                                path.display(),
+                                import.display()
+                            );
+                        }
+                        Ok(import)
+                    }
+                    Ok(None) => paths.resolve_import_and_include_paths(
+                        cwd,
+                        &import_path,
+                        &mut resolved_solc_include_paths,
+                    ),
+                    Err(err) => Err(err),
+                };
+                match resolved {
                     Ok(import) => {
                         add_node(&mut unresolved, &mut index, &mut resolved_imports, import)
                             .map_err(|err| {
@@ -442,6 +591,7 @@ impl<D: ParsedSource> Graph<D> {
             data: Default::default(),
             unresolved_imports,
             resolved_solc_include_paths,
+            import_remappings,
         };
         Ok(Graph { nodes, edges, root: paths.root.clone() })
     }
@@ -454,6 +604,22 @@ impl<D: ParsedSource> Graph<D> {
     }
 }
 
+impl Graph<SolData> {
+    /// Returns all contracts, interfaces and libraries of all files in the graph, together with the
+    /// file they're defined in.
+    ///
+    /// This is a lightweight index built from the parsed sources, see [`SolContract`], and doesn't
+    /// require compiling the project.
+    ///
+    /// File-level free functions, errors, events and other definitions outside of a contract are
+    /// not included, see [`SolData::definitions`] for all file-level definitions of a file.
+    pub fn contracts(&self) -> impl Iterator<Item = (&Path, &SolContract)> {
+        self.nodes
+            .iter()
+            .flat_map(|node| node.data.contracts.iter().map(move |c| (node.path.as_path(), c)))
+    }
+}
+
 impl<D: ParsedSource> Graph<D> {
     /// Consumes the nodes of the graph and returns all input files together with their appropriate
     /// version and the edges of the graph
@@ -465,6 +631,20 @@ impl<D: ParsedSource> Graph<D> {
         offline: bool,
         version_manager: &VM,
     ) -> Result<(VersionedSources, GraphEdges<D>)>
+    where
+        VM::Compiler: Compiler<ParsedSource = D>,
+    {
+        self.into_sources_by_version_with_policy(offline, version_manager, &Default::default())
+    }
+
+    /// Same as [`Self::into_sources_by_version()`] but selects the compiler versions according to
+    /// the given [`VersionSelectionPolicy`].
+    pub fn into_sources_by_version_with_policy<VM: CompilerVersionManager>(
+        self,
+        offline: bool,
+        version_manager: &VM,
+        policy: &VersionSelectionPolicy,
+    ) -> Result<(VersionedSources, GraphEdges<D>)>
     where
         VM::Compiler: Compiler<ParsedSource = D>,
     {
@@ -498,7 +678,7 @@ impl<D: ParsedSource> Graph<D> {
             }
         }
 
-        let versioned_nodes = self.get_input_node_versions(offline, version_manager)?;
+        let versioned_nodes = self.get_input_node_versions(offline, version_manager, policy)?;
         let (nodes, edges) = self.split();
 
         let mut versioned_sources = HashMap::with_capacity(versioned_nodes.len());
@@ -530,6 +710,34 @@ impl<D: ParsedSource> Graph<D> {
         Ok((VersionedSources { inner: versioned_sources, offline }, edges))
     }
 
+    /// Returns all compiler versions each node of the graph would be compiled with.
+    ///
+    /// Input files are compiled with exactly one version, library files with the versions of all
+    /// input files that import them, see [`Self::into_sources_by_version_with_policy()`].
+    ///
+    /// The `policy` should be the [`VersionSelectionPolicy`] of the project, so that the versions
+    /// match the ones the project is compiled with.
+    pub fn node_compiler_versions<VM: CompilerVersionManager>(
+        &self,
+        offline: bool,
+        version_manager: &VM,
+        policy: &VersionSelectionPolicy,
+    ) -> Result<HashMap<usize, BTreeSet<semver::Version>>>
+    where
+        VM::Compiler: Compiler<ParsedSource = D>,
+    {
+        let versioned_nodes = self.get_input_node_versions(offline, version_manager, policy)?;
+        let mut versions: HashMap<_, BTreeSet<_>> = HashMap::new();
+        for (version, input_node_indices) in versioned_nodes {
+            for idx in input_node_indices {
+                for node in self.node_ids(idx) {
+                    versions.entry(node).or_default().insert(version.as_ref().clone());
+                }
+            }
+        }
+        Ok(versions)
+    }
+
     /// Writes the list of imported files into the given formatter:
     ///
     /// ```text
@@ -582,7 +790,8 @@ impl<D: ParsedSource> Graph<D> {
     ///
     /// Returns an error message with __all__ input files that don't have compatible imports.
     ///
-    /// This also attempts to prefer local installations over remote available.
+    /// The version is selected according to the `policy`, which by default prefers local
+    /// installations over remote available.
     /// If `offline` is set to `true` then only already installed.
     fn get_input_node_versions<
         VM: CompilerVersionManager<Compiler = C>,
@@ -591,6 +800,7 @@ impl<D: ParsedSource> Graph<D> {
         &self,
         offline: bool,
         version_manager: &VM,
+        policy: &VersionSelectionPolicy,
     ) -> Result<HashMap<CompilerVersion, Vec<usize>>> {
         trace!("resolving input node versions");
         // this is likely called by an application and will be eventually printed so we don't exit
@@ -618,28 +828,57 @@ impl<D: ParsedSource> Graph<D> {
             // all its imports
             self.retain_compatible_versions(idx, &mut candidates);
 
+            let node = self.node(idx);
+            if let Some(pinned) = policy.pinned_version(&node.path, &self.root) {
+                if !candidates.is_empty() {
+                    candidates.retain(|v| v.as_ref() == pinned);
+                    if candidates.is_empty() {
+                        let f = utils::source_name(&node.path, &self.root).display();
+                        let satisfies = self.node_ids(idx).all(|node| {
+                            self.node(node)
+                                .data
+                                .version_req()
+                                .map_or(true, |req| req.matches(pinned))
+                        });
+                        if !satisfies {
+                            errors.push(format!(
+                                "Pinned compiler version {pinned} does not satisfy the version requirements of {f}"
+                            ));
+                        } else if offline {
+                            errors.push(format!(
+                                "Pinned compiler version {pinned} of {f} is not installed"
+                            ));
+                        } else {
+                            errors.push(format!(
+                                "Pinned compiler version {pinned} of {f} is not available"
+                            ));
+                        }
+                        erroneous_nodes.insert(idx);
+                        continue;
+                    }
+                }
+            }
+
             if candidates.is_empty() && !erroneous_nodes.contains(&idx) {
                 // check if the version is even valid
-                let node = self.node(idx);
                 if let Err(version_err) = node.check_available_version(&all_versions, offline) {
                     let f = utils::source_name(&node.path, &self.root).display();
                     errors.push(format!("Encountered invalid solc version in {f}: {version_err}"));
                 } else {
                     let mut msg = String::new();
                     self.format_imports_list(idx, &mut msg).unwrap();
+                    let versions =
+                        all_versions.iter().map(|v| v.as_ref().clone()).collect::<Vec<_>>();
+                    if let Some(conflict) = self.explain_version_conflict(idx, &versions) {
+                        write!(msg, "\n{conflict}").unwrap();
+                    }
                     errors.push(format!("Found incompatible Solidity versions:\n{msg}"));
                 }
 
                 erroneous_nodes.insert(idx);
             } else {
-                // found viable candidates, pick the most recent version that's already installed
-                let candidate =
-                    if let Some(pos) = candidates.iter().rposition(|v| v.is_installed()) {
-                        candidates[pos]
-                    } else {
-                        candidates.last().expect("not empty; qed.")
-                    }
-                    .clone();
+                // found viable candidates, pick the version preferred by the policy
+                let candidate = candidates[policy.select(&candidates)].clone();
 
                 // also store all possible candidates to optimize the set
                 all_candidates.push((idx, candidates.into_iter().collect::<HashSet<_>>()));
@@ -651,7 +890,18 @@ impl<D: ParsedSource> Graph<D> {
         // detected multiple versions but there might still exist a single version that satisfies
         // all sources
         if versioned_nodes.len() > 1 {
-            versioned_nodes = Self::resolve_multiple_versions(all_candidates);
+            versioned_nodes = Self::resolve_multiple_versions(all_candidates, policy);
+        }
+
+        if policy.is_single_version() && versioned_nodes.len() > 1 {
+            let mut msg = String::from("Sources require multiple compiler versions:");
+            for (version, nodes) in &versioned_nodes {
+                write!(msg, "\n{version}:").unwrap();
+                for idx in nodes {
+                    write!(msg, "\n    {}", self.display_node(*idx)).unwrap();
+                }
+            }
+            errors.push(msg);
         }
 
         if versioned_nodes.len() == 1 {
@@ -677,6 +927,7 @@ impl<D: ParsedSource> Graph<D> {
     /// a high chance that the number of source files is <50, even for larger projects.
     fn resolve_multiple_versions(
         all_candidates: Vec<(usize, HashSet<&CompilerVersion>)>,
+        policy: &VersionSelectionPolicy,
     ) -> HashMap<CompilerVersion, Vec<usize>> {
         // returns the intersection as sorted set of nodes
         fn intersection<'a>(
@@ -696,18 +947,16 @@ impl<D: ParsedSource> Graph<D> {
             v
         }
 
-        /// returns the highest version that is installed
+        /// returns the version preferred by the policy, by default the highest version that is
+        /// installed
         /// if the candidates set only contains uninstalled versions then this returns the highest
         /// uninstalled version
-        fn remove_candidate(candidates: &mut Vec<&CompilerVersion>) -> CompilerVersion {
+        fn remove_candidate(
+            candidates: &mut Vec<&CompilerVersion>,
+            policy: &VersionSelectionPolicy,
+        ) -> CompilerVersion {
             debug_assert!(!candidates.is_empty());
-
-            if let Some(pos) = candidates.iter().rposition(|v| v.is_installed()) {
-                candidates.remove(pos)
-            } else {
-                candidates.pop().expect("not empty; qed.")
-            }
-            .clone()
+            candidates.remove(policy.select(candidates)).clone()
         }
 
         let all_sets = all_candidates.iter().map(|(_, versions)| versions).collect();
@@ -715,7 +964,7 @@ impl<D: ParsedSource> Graph<D> {
         // find all versions that satisfy all nodes
         let mut intersection = intersection(all_sets);
         if !intersection.is_empty() {
-            let exact_version = remove_candidate(&mut intersection);
+            let exact_version = remove_candidate(&mut intersection, policy);
             let all_nodes = all_candidates.into_iter().map(|(node, _)| node).collect();
             trace!("resolved solc version compatible with all sources  \"{}\"", exact_version);
             return HashMap::from([(exact_version, all_nodes)]);
@@ -731,14 +980,18 @@ impl<D: ParsedSource> Graph<D> {
             let mut versions = versions.into_iter().collect::<Vec<_>>();
             versions.sort_unstable();
 
-            let candidate = if let Some(idx) =
-                versions.iter().rposition(|v| versioned_nodes.contains_key(*v))
-            {
-                // use a version that's already in the set
+            let candidate = if let Some(idx) = match policy {
+                VersionSelectionPolicy::Minimal => {
+                    versions.iter().position(|v| versioned_nodes.contains_key(*v))
+                }
+                _ => versions.iter().rposition(|v| versioned_nodes.contains_key(*v)),
+            } {
+                // use a version that's already in the set, the highest one unless the policy
+                // prefers the lowest
                 versions.remove(idx).clone()
             } else {
-                // use the highest version otherwise
-                remove_candidate(&mut versions)
+                // use the version preferred by the policy otherwise
+                remove_candidate(&mut versions, policy)
             };
 
             versioned_nodes.entry(candidate).or_insert_with(|| Vec::with_capacity(1)).push(node);
@@ -863,6 +1116,11 @@ impl<D: ParsedSource> Node<D> {
         Ok(Self { path: file.to_path_buf(), source, data })
     }
 
+    /// Returns the path of the file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     pub fn content(&self) -> &str {
         &self.source.content
     }
@@ -928,6 +1186,7 @@ mod tests {
     use crate::Solc;
 
     use super::*;
+    use std::collections::BTreeMap;
 
     #[test]
     fn can_resolve_hardhat_dependency_graph() {
@@ -1000,6 +1259,245 @@ src/Dapp.t.sol >=0.6.6
         );
     }
 
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn can_export_dapp_sample_graph() {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test-data/dapp-sample");
+        let paths = ProjectPathsConfig::<Solc>::dapptools(root).unwrap();
+        let graph = Graph::resolve(&paths).unwrap();
+        let version = semver::Version::new(0, 8, 19);
+        let versions =
+            graph.files().values().map(|idx| (*idx, BTreeSet::from([version.clone()]))).collect();
+        let export = graph.export().with_compiler_versions(&versions);
+
+        let mut out = Vec::<u8>::new();
+        export.write(ExportFormat::Dot, &mut out).unwrap();
+        assert_eq!(
+            r#"digraph {
+    0 [label="src/Dapp.sol\n>=0.6.6\n0.8.19"];
+    1 [label="src/Dapp.t.sol\n>=0.6.6\n0.8.19"];
+    2 [label="lib/ds-test/src/test.sol\n>=0.4.23\n0.8.19", style=dashed];
+    1 -> 2;
+    1 -> 0;
+}
+"#,
+            String::from_utf8(out).unwrap()
+        );
+
+        let mut out = Vec::<u8>::new();
+        export.write(ExportFormat::Mermaid, &mut out).unwrap();
+        assert_eq!(
+            r#"graph TD
+    n0["src/Dapp.sol<br/>>=0.6.6<br/>0.8.19"]
+    n1["src/Dapp.t.sol<br/>>=0.6.6<br/>0.8.19"]
+    n2["lib/ds-test/src/test.sol<br/>>=0.4.23<br/>0.8.19"]
+    n1 --> n2
+    n1 --> n0
+    classDef library stroke-dasharray: 5 5
+    class n2 library
+"#,
+            String::from_utf8(out).unwrap()
+        );
+
+        let mut out = Vec::<u8>::new();
+        export.write(ExportFormat::Json, &mut out).unwrap();
+        let parsed: ExportedGraph = serde_json::from_slice(&out).unwrap();
+        assert_eq!(parsed, export);
+        assert!(parsed.nodes[2].is_library);
+        assert_eq!(parsed.nodes[1].imports, vec![2, 0]);
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn can_explain_version_conflict() {
+        let tmp = tempfile::tempdir().unwrap();
+        let src = tmp.path().join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(src.join("A.sol"), "pragma solidity =0.8.10;\nimport \"./B.sol\";").unwrap();
+        std::fs::write(src.join("B.sol"), "pragma solidity ^0.8.0;\nimport \"./C.sol\";").unwrap();
+        std::fs::write(src.join("C.sol"), "pragma solidity >=0.8.11;").unwrap();
+        std::fs::write(src.join("D.sol"), "pragma solidity ^0.8.0;\nimport \"./E.sol\";").unwrap();
+        std::fs::write(src.join("E.sol"), "pragma solidity =0.8.12-nightly;").unwrap();
+        let paths = ProjectPathsConfig::<Solc>::dapptools(tmp.path()).unwrap();
+        let graph = Graph::resolve(&paths).unwrap();
+
+        let idx = graph.files()[&src.join("A.sol")];
+        let versions = (9..=12).map(|patch| semver::Version::new(0, 8, patch)).collect::<Vec<_>>();
+        let conflict = graph.explain_version_conflict(idx, &versions).unwrap();
+
+        assert_eq!(conflict.requirements.len(), 3);
+        let (a, b) = conflict.conflict.unwrap();
+        assert_eq!(conflict.requirements[a].file, PathBuf::from("src/A.sol"));
+        assert_eq!(conflict.requirements[b].file, PathBuf::from("src/C.sol"));
+        assert_eq!(
+            conflict.requirements[b].import_chain,
+            vec![PathBuf::from("src/A.sol"), "src/B.sol".into(), "src/C.sol".into()]
+        );
+        assert_eq!(conflict.suggested_version, Some(semver::Version::new(0, 8, 12)));
+        assert_eq!(conflict.relax, vec![a]);
+        assert_eq!(
+            conflict.to_string(),
+            "src/A.sol can't be compiled, these requirements have no version in common:
+    src/A.sol =0.8.10
+    src/C.sol >=0.8.11 (imported via src/A.sol -> src/B.sol -> src/C.sol)
+Relax the following pragmas to compile with 0.8.12:
+    src/A.sol =0.8.10"
+        );
+
+        let idx = graph.files()[&src.join("B.sol")];
+        assert!(graph.explain_version_conflict(idx, &versions).is_none());
+
+        // `^0.8.0` matches the versions around the pre-release, but not the pre-release itself
+        let idx = graph.files()[&src.join("D.sol")];
+        let mut versions = versions;
+        versions.push("0.8.12-nightly".parse().unwrap());
+        let conflict = graph.explain_version_conflict(idx, &versions).unwrap();
+        assert_eq!(conflict.conflict, Some((0, 1)));
+        assert_eq!(conflict.requirements[1].file, PathBuf::from("src/E.sol"));
+        assert_eq!(conflict.suggested_version, Some(semver::Version::new(0, 8, 12)));
+        assert_eq!(conflict.relax, vec![1]);
+    }
+
+    #[derive(Debug)]
+    struct MockVersionManager(Vec<CompilerVersion>);
+
+    impl CompilerVersionManager for MockVersionManager {
+        type Compiler = Solc;
+
+        fn all_versions(&self) -> Vec<CompilerVersion> {
+            self.0.clone()
+        }
+
+        fn installed_versions(&self) -> Vec<CompilerVersion> {
+            self.0.iter().filter(|v| v.is_installed()).cloned().collect()
+        }
+
+        fn install(
+            &self,
+            version: &semver::Version,
+        ) -> std::result::Result<Solc, crate::compilers::VersionManagerError> {
+            Err(crate::compilers::VersionManagerError::VersionNotInstalled(version.clone()))
+        }
+
+        fn get_installed(
+            &self,
+            version: &semver::Version,
+        ) -> std::result::Result<Solc, crate::compilers::VersionManagerError> {
+            Err(crate::compilers::VersionManagerError::VersionNotInstalled(version.clone()))
+        }
+    }
+
+    #[test]
+    fn can_select_versions_by_policy() {
+        let tmp = tempfile::tempdir().unwrap();
+        let src = tmp.path().join("src");
+        let test = tmp.path().join("test");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::create_dir_all(&test).unwrap();
+        std::fs::write(src.join("A.sol"), "pragma solidity >=0.8.0 <0.8.12;").unwrap();
+        std::fs::write(src.join("B.sol"), "pragma solidity >=0.8.10;").unwrap();
+        let paths = ProjectPathsConfig::<Solc>::dapptools(tmp.path()).unwrap();
+        let vm = MockVersionManager(vec![
+            CompilerVersion::Installed(semver::Version::new(0, 8, 10)),
+            CompilerVersion::Remote(semver::Version::new(0, 8, 11)),
+            CompilerVersion::Remote(semver::Version::new(0, 8, 12)),
+        ]);
+
+        let versions = |policy: VersionSelectionPolicy| {
+            let graph = Graph::resolve(&paths).unwrap();
+            let (sources, _) =
+                graph.into_sources_by_version_with_policy(false, &vm, &policy).unwrap();
+            let mut versions = sources
+                .inner
+                .into_iter()
+                .map(|(v, sources)| (v.as_ref().to_string(), sources.len()))
+                .collect::<Vec<_>>();
+            versions.sort();
+            versions
+        };
+
+        assert_eq!(versions(VersionSelectionPolicy::PreferInstalled), [("0.8.10".into(), 2)]);
+        assert_eq!(versions(VersionSelectionPolicy::Latest), [("0.8.11".into(), 2)]);
+        assert_eq!(versions(VersionSelectionPolicy::Minimal), [("0.8.10".into(), 2)]);
+        assert_eq!(versions(VersionSelectionPolicy::SingleVersion), [("0.8.10".into(), 2)]);
+
+        std::fs::write(test.join("C.t.sol"), "pragma solidity =0.8.12;").unwrap();
+        assert_eq!(
+            versions(VersionSelectionPolicy::PreferInstalled),
+            [("0.8.10".into(), 2), ("0.8.12".into(), 1)]
+        );
+        let pinned = VersionSelectionPolicy::PinnedPerDirectory(BTreeMap::from([(
+            PathBuf::from("src"),
+            semver::Version::new(0, 8, 11),
+        )]));
+        assert_eq!(versions(pinned), [("0.8.11".into(), 2), ("0.8.12".into(), 1)]);
+
+        let graph = Graph::resolve(&paths).unwrap();
+        let err = graph
+            .into_sources_by_version_with_policy(false, &vm, &VersionSelectionPolicy::SingleVersion)
+            .unwrap_err();
+        assert!(err.to_string().contains("Sources require multiple compiler versions"), "{err}");
+
+        let graph = Graph::resolve(&paths).unwrap();
+        let pinned = VersionSelectionPolicy::PinnedPerDirectory(BTreeMap::from([(
+            PathBuf::from("test"),
+            semver::Version::new(0, 8, 11),
+        )]));
+        let err = graph.into_sources_by_version_with_policy(false, &vm, &pinned).unwrap_err();
+        assert!(
+            err.to_string().contains(
+                "Pinned compiler version 0.8.11 does not satisfy the version requirements of test/C.t.sol"
+            ),
+            "{err}"
+        );
+
+        // the pinned version satisfies the requirements, but isn't installed
+        std::fs::remove_file(test.join("C.t.sol")).unwrap();
+        let graph = Graph::resolve(&paths).unwrap();
+        let pinned = VersionSelectionPolicy::PinnedPerDirectory(BTreeMap::from([(
+            PathBuf::from("src"),
+            semver::Version::new(0, 8, 11),
+        )]));
+        let err = graph.into_sources_by_version_with_policy(true, &vm, &pinned).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Pinned compiler version 0.8.11 of src/A.sol is not installed"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn prefers_highest_version_in_set_by_default() {
+        let tmp = tempfile::tempdir().unwrap();
+        let src = tmp.path().join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(src.join("A.sol"), "pragma solidity =0.8.12;").unwrap();
+        std::fs::write(src.join("B.sol"), "pragma solidity =0.8.10;").unwrap();
+        std::fs::write(src.join("C.sol"), "pragma solidity >=0.8.10;").unwrap();
+        let paths = ProjectPathsConfig::<Solc>::dapptools(tmp.path()).unwrap();
+        let vm = MockVersionManager(vec![
+            CompilerVersion::Installed(semver::Version::new(0, 8, 10)),
+            CompilerVersion::Remote(semver::Version::new(0, 8, 12)),
+        ]);
+
+        // `C.sol` uses the highest version that's already needed, even if it's not installed
+        let graph = Graph::resolve(&paths).unwrap();
+        let versions =
+            graph.node_compiler_versions(false, &vm, &VersionSelectionPolicy::default()).unwrap();
+        let version = |file: &str| {
+            let node = graph.files()[&src.join(file)];
+            versions[&node].iter().map(ToString::to_string).collect::<Vec<_>>()
+        };
+        assert_eq!(version("A.sol"), ["0.8.12"]);
+        assert_eq!(version("B.sol"), ["0.8.10"]);
+        assert_eq!(version("C.sol"), ["0.8.12"]);
+
+        let versions =
+            graph.node_compiler_versions(false, &vm, &VersionSelectionPolicy::Minimal).unwrap();
+        let node = graph.files()[&src.join("C.sol")];
+        assert_eq!(versions[&node].iter().map(ToString::to_string).collect::<Vec<_>>(), ["0.8.10"]);
+    }
+
     #[test]
     #[cfg(not(target_os = "windows"))]
     fn can_print_hardhat_sample_graph() {
@@ -1017,4 +1515,68 @@ src/Dapp.t.sol >=0.6.6
             String::from_utf8(out).unwrap()
         );
     }
+
+    #[test]
+    fn can_find_affected_contracts() {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test-data/dapp-sample");
+        let paths = ProjectPathsConfig::<Solc>::dapptools(&root).unwrap();
+        let graph = Graph::<SolData>::resolve(&paths).unwrap();
+
+        let dapp = root.join("src/Dapp.sol");
+        let test = root.join("src/Dapp.t.sol");
+        let ds_test = root.join("lib/ds-test/src/test.sol");
+
+        assert_eq!(graph.all_importers(&ds_test), HashSet::from([&test]));
+        assert!(graph.all_importers(&test).is_empty());
+
+        let affected = graph.affected_contracts([&ds_test, &root.join("src/Missing.sol")]);
+        assert_eq!(
+            affected,
+            BTreeMap::from([
+                (ds_test.clone(), vec!["DSTest".to_string()]),
+                (test.clone(), vec!["DappTest".to_string()]),
+            ])
+        );
+
+        let (_, edges) = graph.into_sources();
+        assert_eq!(
+            edges.affected_contracts([&dapp]),
+            BTreeMap::from([
+                (dapp.clone(), vec!["Dapp".to_string()]),
+                (test, vec!["DappTest".to_string()]),
+            ])
+        );
+    }
+
+    #[test]
+    fn can_resolve_imports_with_custom_resolver() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = utils::canonicalized(tmp.path());
+        let src = root.join("src");
+        let vendor = root.join("vendor/token");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::create_dir_all(&vendor).unwrap();
+        std::fs::write(src.join("A.sol"), "import \"pkg:token/Token.sol\";\nimport \"./B.sol\";")
+            .unwrap();
+        std::fs::write(src.join("B.sol"), "contract B {}").unwrap();
+        std::fs::write(vendor.join("Token.sol"), "contract Token {}").unwrap();
+
+        let registry = root.join("vendor");
+        let paths = ProjectPathsConfig::builder()
+            .import_resolver(move |_: &Path, import: &Path| {
+                Ok(import.to_str().and_then(|i| i.strip_prefix("pkg:")).map(|i| registry.join(i)))
+            })
+            .build_with_root::<Solc>(&root);
+        let graph = Graph::<SolData>::resolve(&paths).unwrap();
+
+        assert_eq!(
+            graph.imports(src.join("A.sol")),
+            HashSet::from([&vendor.join("Token.sol"), &src.join("B.sol")])
+        );
+        assert_eq!(
+            graph.edges.import_remappings().iter().map(|r| r.to_string()).collect::<Vec<_>>(),
+            vec!["src/A.sol:pkg:token/=vendor/token/".to_string()]
+        );
+        assert!(graph.edges.unresolved_imports().is_empty());
+    }
 }
@@ -0,0 +1,243 @@
+//! Node-style resolution of imports from `node_modules`.
+
+use super::ImportResolver;
+use crate::{error::Result, utils};
+use serde_json::Value;
+use std::path::{Component, Path, PathBuf};
+
+/// Resolves package imports like `@scope/pkg/contracts/Token.sol` the way Node does.
+///
+/// Starting at the directory of the importing file, every ancestor directory is checked for a
+/// `node_modules/<package>/package.json`, so the nearest installation of a package wins. This
+/// supports packages installed under an alias, multiple versions of a package nested in
+/// sub-`node_modules` and monorepos where packages are hoisted to the workspace root, without any
+/// remappings.
+///
+/// If the `package.json` has an [`exports`](https://nodejs.org/api/packages.html#exports) field,
+/// the import is resolved against it, using the first of the configured conditions that matches.
+/// Since most packages only export their JS files, an import that isn't exported falls back to the
+/// file relative to the package root.
+///
+/// Resolved paths are canonicalized, so packages linked by pnpm or workspaces resolve their own
+/// dependencies relative to their real location, like Node.
+///
+/// # Examples
+///
+/// ```no_run
+/// use foundry_compilers::{resolver::NodeModulesResolver, ProjectPathsConfig};
+///
+/// let paths = ProjectPathsConfig::builder()
+///     .import_resolver(NodeModulesResolver::default())
+///     .build_with_root::<foundry_compilers::Solc>(".");
+/// ```
+#[derive(Debug, Clone)]
+pub struct NodeModulesResolver {
+    /// The `exports` conditions, in order of priority
+    conditions: Vec<String>,
+}
+
+impl Default for NodeModulesResolver {
+    fn default() -> Self {
+        Self { conditions: vec!["solidity".to_string(), "default".to_string()] }
+    }
+}
+
+impl NodeModulesResolver {
+    /// Creates a resolver that matches the `solidity` and `default` export conditions
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the export conditions to match, in order of priority
+    #[must_use]
+    pub fn with_conditions(
+        mut self,
+        conditions: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.conditions = conditions.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Returns the target of the `./<subpath>` export of the package, relative to the package root.
+    fn resolve_export(&self, exports: &Value, subpath: &str) -> Option<String> {
+        // a string or an object without subpath keys only exports the main entry point `.`
+        let Value::Object(exports) = exports else { return None };
+        if !exports.keys().any(|key| key.starts_with('.')) {
+            return None;
+        }
+
+        let request = format!("./{subpath}");
+        if let Some(target) = exports.get(&request) {
+            return self.resolve_target(target, None);
+        }
+
+        // the pattern with the longest prefix wins, directory exports end with `/`
+        exports
+            .iter()
+            .filter_map(|(key, target)| {
+                if let Some((prefix, suffix)) = key.split_once('*') {
+                    let rest = request.strip_prefix(prefix)?;
+                    let matched = rest.strip_suffix(suffix)?;
+                    Some((prefix.len(), target, matched.to_string(), true))
+                } else if key.ends_with('/') {
+                    let rest = request.strip_prefix(key.as_str())?;
+                    Some((key.len(), target, rest.to_string(), false))
+                } else {
+                    None
+                }
+            })
+            .max_by_key(|(len, ..)| *len)
+            .and_then(|(_, target, matched, is_pattern)| {
+                if is_pattern {
+                    self.resolve_target(target, Some(&matched))
+                } else {
+                    self.resolve_target(target, None).map(|dir| format!("{dir}{matched}"))
+                }
+            })
+    }
+
+    /// Resolves a target of the `exports` field, replacing `*` with `matched`.
+    fn resolve_target(&self, target: &Value, matched: Option<&str>) -> Option<String> {
+        match target {
+            Value::String(target) if target.starts_with("./") => {
+                Some(matched.map_or_else(|| target.clone(), |m| target.replace('*', m)))
+            }
+            Value::Array(targets) => targets.iter().find_map(|t| self.resolve_target(t, matched)),
+            Value::Object(conditions) => self
+                .conditions
+                .iter()
+                .filter_map(|condition| conditions.get(condition))
+                .find_map(|t| self.resolve_target(t, matched)),
+            _ => None,
+        }
+    }
+}
+
+impl ImportResolver for NodeModulesResolver {
+    fn resolve_import(&self, cwd: &Path, import: &Path) -> Result<Option<PathBuf>> {
+        let Some((package, subpath)) = split_package_import(import) else { return Ok(None) };
+
+        for dir in cwd.ancestors() {
+            if dir.file_name().map_or(false, |name| name == "node_modules") {
+                continue;
+            }
+            let package_root = dir.join("node_modules").join(&package);
+            let manifest = package_root.join("package.json");
+            if !manifest.is_file() {
+                continue;
+            }
+
+            let manifest: Value = utils::read_json_file(&manifest)?;
+            let exported = manifest
+                .get("exports")
+                .and_then(|exports| self.resolve_export(exports, &subpath))
+                .map(|target| package_root.join(target))
+                .filter(|path| path.is_file());
+            let resolved = exported.unwrap_or_else(|| package_root.join(&subpath));
+            if resolved.is_file() {
+                return Ok(Some(utils::canonicalized(resolved)));
+            }
+
+            // the nearest installation of the package shadows all others
+            trace!("\"{}\" not found in package {}", subpath, package_root.display());
+            return Ok(None);
+        }
+        Ok(None)
+    }
+}
+
+/// Splits an import into the package name, e.g. `pkg` or `@scope/pkg`, and the path within the
+/// package.
+fn split_package_import(import: &Path) -> Option<(String, String)> {
+    let mut components = Vec::new();
+    for component in import.components() {
+        match component {
+            Component::Normal(c) => components.push(c.to_str()?),
+            _ => return None,
+        }
+    }
+    let name_len = if components.first()?.starts_with('@') { 2 } else { 1 };
+    if components.len() <= name_len || components[..name_len].iter().any(|c| c.contains(':')) {
+        return None;
+    }
+    Some((components[..name_len].join("/"), components[name_len..].join("/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write(path: PathBuf, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn can_resolve_node_modules() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = utils::canonicalized(tmp.path());
+        let modules = root.join("node_modules");
+
+        // aliased package
+        write(modules.join("token-v1/package.json"), r#"{ "name": "token" }"#);
+        write(modules.join("token-v1/contracts/Token.sol"), "");
+        // two versions of the same package, one nested in the `node_modules` of another package
+        write(modules.join("math/package.json"), r#"{ "version": "1.0.0" }"#);
+        write(modules.join("math/Math.sol"), "");
+        write(modules.join("@scope/lib/package.json"), "{}");
+        write(modules.join("@scope/lib/contracts/Lib.sol"), "");
+        write(
+            modules.join("@scope/lib/node_modules/math/package.json"),
+            r#"{ "version": "2.0.0" }"#,
+        );
+        write(modules.join("@scope/lib/node_modules/math/Math.sol"), "");
+        // package with exports
+        write(
+            modules.join("exported/package.json"),
+            r#"{ "exports": {
+                "./package.json": "./package.json",
+                "./*": { "solidity": "./src/*", "default": "./dist/*.js" },
+                "./legacy/": "./old/"
+            } }"#,
+        );
+        write(modules.join("exported/src/Exported.sol"), "");
+        write(modules.join("exported/old/Legacy.sol"), "");
+        write(modules.join("exported/Unexported.sol"), "");
+
+        let resolver = NodeModulesResolver::default();
+        let resolve =
+            |cwd: &Path, import: &str| resolver.resolve_import(cwd, Path::new(import)).unwrap();
+        let src = root.join("packages/app/contracts");
+
+        assert_eq!(
+            resolve(&src, "token-v1/contracts/Token.sol"),
+            Some(modules.join("token-v1/contracts/Token.sol"))
+        );
+        assert_eq!(resolve(&src, "math/Math.sol"), Some(modules.join("math/Math.sol")));
+        assert_eq!(
+            resolve(&modules.join("@scope/lib/contracts"), "math/Math.sol"),
+            Some(modules.join("@scope/lib/node_modules/math/Math.sol"))
+        );
+        assert_eq!(
+            resolve(&src, "@scope/lib/contracts/Lib.sol"),
+            Some(modules.join("@scope/lib/contracts/Lib.sol"))
+        );
+        assert_eq!(
+            resolve(&src, "exported/Exported.sol"),
+            Some(modules.join("exported/src/Exported.sol"))
+        );
+        assert_eq!(
+            resolve(&src, "exported/legacy/Legacy.sol"),
+            Some(modules.join("exported/old/Legacy.sol"))
+        );
+        assert_eq!(
+            resolve(&src, "exported/Unexported.sol"),
+            Some(modules.join("exported/Unexported.sol"))
+        );
+
+        assert_eq!(resolve(&src, "math/Missing.sol"), None);
+        assert_eq!(resolve(&src, "missing/Missing.sol"), None);
+        assert_eq!(resolve(&src, "pkg:math/Math.sol"), None);
+    }
+}
@@ -1,8 +1,12 @@
 use crate::utils;
 use semver::VersionReq;
-use solang_parser::pt::{
-    ContractPart, ContractTy, FunctionAttribute, FunctionDefinition, Import, ImportPath, Loc,
-    SourceUnitPart, Visibility,
+use solang_parser::{
+    doccomment::{parse_doccomments, DocComment},
+    pt::{
+        CatchClause, CodeLocation, Comment, ContractDefinition, ContractPart, ContractTy,
+        Expression, FunctionAttribute, FunctionDefinition, FunctionTy, Import, ImportPath, Loc,
+        SourceUnitPart, Statement, Visibility,
+    },
 };
 use std::{
     ops::Range,
@@ -17,7 +21,22 @@ pub struct SolData {
     pub experimental: Option<SolDataUnit<String>>,
     pub imports: Vec<SolDataUnit<SolImport>>,
     pub version_req: Option<VersionReq>,
+    /// All libraries defined in the file, in the order of the library contracts in `contracts`.
     pub libraries: Vec<SolLibrary>,
+    /// Index of all contracts, interfaces and libraries defined in the file.
+    ///
+    /// This is empty if the file could not be parsed.
+    pub contracts: Vec<SolContract>,
+    /// All top-level definitions, like contracts, free functions, structs, enums, errors, events,
+    /// user defined value types and constants, located at their definition.
+    ///
+    /// This is empty if the file could not be parsed.
+    pub definitions: Vec<SolDataUnit<SolDefinition>>,
+    /// All pragma directives, including the version and experimental pragmas, located at the
+    /// directive.
+    ///
+    /// This is empty if the file could not be parsed.
+    pub pragmas: Vec<SolDataUnit<String>>,
 }
 
 impl SolData {
@@ -41,12 +60,48 @@ impl SolData {
         let mut experimental = None;
         let mut imports = Vec::<SolDataUnit<SolImport>>::new();
         let mut libraries = Vec::new();
+        let mut contracts = Vec::new();
+        let mut definitions = Vec::new();
+        let mut pragmas = Vec::new();
 
         match solang_parser::parse(content, 0) {
-            Ok((units, _)) => {
+            Ok((units, comments)) => {
+                // doc comments of an item are located between the previous item and the item
+                let mut prev_end = 0;
                 for unit in units.0 {
+                    let start = unit.loc().start();
+                    let docs = parse_natspec(&comments, prev_end, start);
+                    prev_end = unit.loc().end();
+                    let name = match &unit {
+                        SourceUnitPart::ContractDefinition(def) => def.name.as_ref(),
+                        SourceUnitPart::EnumDefinition(def) => def.name.as_ref(),
+                        SourceUnitPart::StructDefinition(def) => def.name.as_ref(),
+                        SourceUnitPart::EventDefinition(def) => def.name.as_ref(),
+                        SourceUnitPart::ErrorDefinition(def) => def.name.as_ref(),
+                        SourceUnitPart::FunctionDefinition(def) => def.name.as_ref(),
+                        SourceUnitPart::VariableDefinition(def) => def.name.as_ref(),
+                        SourceUnitPart::TypeDefinition(def) => Some(&def.name),
+                        _ => None,
+                    };
+                    if let Some(name) = name {
+                        let parameters = match &unit {
+                            SourceUnitPart::FunctionDefinition(def) => Some(
+                                def.params
+                                    .iter()
+                                    .filter_map(|(_, param)| param.as_ref())
+                                    .map(|param| param.ty.to_string())
+                                    .collect(),
+                            ),
+                            _ => None,
+                        };
+                        let definition = SolDefinition { name: name.name.clone(), parameters };
+                        definitions.push(SolDataUnit::from_loc(definition, unit.loc()));
+                    }
                     match unit {
                         SourceUnitPart::PragmaDirective(loc, Some(pragma), Some(value)) => {
+                            let unit = SolDataUnit::from_loc((), loc);
+                            let directive = content.get(unit.loc()).unwrap_or_default();
+                            pragmas.push(SolDataUnit::new(directive.to_string(), unit.loc()));
                             if pragma.name == "solidity" {
                                 // we're only interested in the solidity version pragma
                                 version = Some(SolDataUnit::from_loc(value.string.clone(), loc));
@@ -77,15 +132,21 @@ impl SolData {
                             imports.push(SolDataUnit::from_loc(sol_import, loc));
                         }
                         SourceUnitPart::ContractDefinition(def) => {
-                            let functions = def
-                                .parts
-                                .into_iter()
-                                .filter_map(|part| match part {
-                                    ContractPart::FunctionDefinition(f) => Some(*f),
-                                    _ => None,
-                                })
-                                .collect();
+                            let Some(contract) = SolContract::new(&def, &comments, docs) else {
+                                continue;
+                            };
+                            contracts.push(contract);
+                            // tracked together so that the libraries line up with the library
+                            // contracts
                             if let ContractTy::Library(_) = def.ty {
+                                let functions = def
+                                    .parts
+                                    .into_iter()
+                                    .filter_map(|part| match part {
+                                        ContractPart::FunctionDefinition(f) => Some(*f),
+                                        _ => None,
+                                    })
+                                    .collect();
                                 libraries.push(SolLibrary { functions });
                             }
                         }
@@ -113,7 +174,17 @@ impl SolData {
         });
         let version_req = version.as_ref().and_then(|v| Self::parse_version_req(v.data()).ok());
 
-        Self { version_req, version, experimental, imports, license, libraries }
+        Self {
+            version_req,
+            version,
+            experimental,
+            imports,
+            license,
+            libraries,
+            contracts,
+            definitions,
+            pragmas,
+        }
     }
 
     /// Returns the corresponding SemVer version requirement for the solidity version.
@@ -136,6 +207,24 @@ impl SolData {
     }
 }
 
+/// A top-level definition of a solidity file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolDefinition {
+    pub name: String,
+    /// The parameter types of a free function, which can be overloaded by free functions with
+    /// different parameter types
+    pub parameters: Option<Vec<String>>,
+}
+
+impl SolDefinition {
+    /// Returns `true` if both definitions can't be declared in the same scope, which is the case if
+    /// they have the same name, unless they're overloaded functions.
+    pub fn collides_with(&self, other: &Self) -> bool {
+        self.name == other.name
+            && !matches!((&self.parameters, &other.parameters), (Some(a), Some(b)) if a != b)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SolImport {
     path: PathBuf,
@@ -197,6 +286,304 @@ impl SolLibrary {
     }
 }
 
+/// Returns the name of the contract that's created by a `new` expression, or `None` if it's not a
+/// contract, like `new bytes(1)`.
+///
+/// Qualified names like `new Lib.Token()` are reduced to the contract name.
+fn deployed_contract(ty: &Expression) -> Option<&str> {
+    match ty {
+        // `new Token(..)`, `new Token{salt: ..}(..)`
+        Expression::FunctionCall(_, ty, _)
+        | Expression::NamedFunctionCall(_, ty, _)
+        | Expression::FunctionCallBlock(_, ty, _) => deployed_contract(ty),
+        Expression::Variable(name) | Expression::MemberAccess(_, _, name) => Some(&name.name),
+        _ => None,
+    }
+}
+
+/// Calls `f` with every expression of the statement, including nested expressions.
+///
+/// Inline assembly is skipped.
+fn walk_statement(stmt: &Statement, f: &mut impl FnMut(&Expression)) {
+    match stmt {
+        Statement::Block { statements, .. } => {
+            statements.iter().for_each(|stmt| walk_statement(stmt, f))
+        }
+        Statement::Args(_, args) | Statement::RevertNamedArgs(_, _, args) => {
+            args.iter().for_each(|arg| walk_expression(&arg.expr, f))
+        }
+        Statement::If(_, cond, then, otherwise) => {
+            walk_expression(cond, f);
+            walk_statement(then, f);
+            otherwise.iter().for_each(|stmt| walk_statement(stmt, f));
+        }
+        Statement::While(_, cond, body) | Statement::DoWhile(_, body, cond) => {
+            walk_expression(cond, f);
+            walk_statement(body, f);
+        }
+        Statement::Expression(_, expr) | Statement::Emit(_, expr) => walk_expression(expr, f),
+        Statement::VariableDefinition(_, _, expr) | Statement::Return(_, expr) => {
+            expr.iter().for_each(|expr| walk_expression(expr, f))
+        }
+        Statement::For(_, init, cond, next, body) => {
+            init.iter().for_each(|stmt| walk_statement(stmt, f));
+            cond.iter().chain(next).for_each(|expr| walk_expression(expr, f));
+            body.iter().for_each(|stmt| walk_statement(stmt, f));
+        }
+        Statement::Revert(_, _, args) => args.iter().for_each(|arg| walk_expression(arg, f)),
+        Statement::Try(_, expr, returns, catches) => {
+            walk_expression(expr, f);
+            returns.iter().for_each(|(_, stmt)| walk_statement(stmt, f));
+            for catch in catches {
+                match catch {
+                    CatchClause::Simple(_, _, stmt) | CatchClause::Named(_, _, _, stmt) => {
+                        walk_statement(stmt, f)
+                    }
+                }
+            }
+        }
+        Statement::Assembly { .. }
+        | Statement::Continue(_)
+        | Statement::Break(_)
+        | Statement::Error(_) => {}
+    }
+}
+
+/// Calls `f` with the expression and all its nested expressions.
+fn walk_expression(expr: &Expression, f: &mut impl FnMut(&Expression)) {
+    f(expr);
+    match expr {
+        Expression::MemberAccess(_, expr, _) => walk_expression(expr, f),
+        Expression::ConditionalOperator(_, cond, a, b) => {
+            [cond, a, b].into_iter().for_each(|expr| walk_expression(expr, f))
+        }
+        Expression::ArraySubscript(_, expr, index) => {
+            walk_expression(expr, f);
+            index.iter().for_each(|expr| walk_expression(expr, f));
+        }
+        Expression::ArraySlice(_, expr, start, end) => {
+            walk_expression(expr, f);
+            start.iter().chain(end).for_each(|expr| walk_expression(expr, f));
+        }
+        Expression::FunctionCall(_, callee, args) => {
+            walk_expression(callee, f);
+            args.iter().for_each(|arg| walk_expression(arg, f));
+        }
+        Expression::FunctionCallBlock(_, callee, block) => {
+            walk_expression(callee, f);
+            walk_statement(block, f);
+        }
+        Expression::NamedFunctionCall(_, callee, args) => {
+            walk_expression(callee, f);
+            args.iter().for_each(|arg| walk_expression(&arg.expr, f));
+        }
+        Expression::ArrayLiteral(_, exprs) => {
+            exprs.iter().for_each(|expr| walk_expression(expr, f))
+        }
+        _ => {
+            let (left, right) = expr.components();
+            left.into_iter().chain(right).for_each(|expr| walk_expression(expr, f));
+        }
+    }
+}
+
+/// A contract, interface or library defined in a solidity file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolContract {
+    pub name: String,
+    pub kind: SolContractKind,
+    /// The names of the inherited contracts, in declaration order
+    pub bases: Vec<String>,
+    /// All functions, modifiers, events and errors of the contract, in declaration order
+    pub items: Vec<SolItem>,
+    /// The NatSpec tags of the contract
+    pub natspec: Vec<NatSpecTag>,
+    /// The names of the contracts created with `new` in the contract, in order of appearance
+    pub deploys: Vec<String>,
+    /// The byte range of the contract definition in the file
+    pub loc: Range<usize>,
+}
+
+impl SolContract {
+    fn new(
+        def: &ContractDefinition,
+        comments: &[Comment],
+        natspec: Vec<NatSpecTag>,
+    ) -> Option<Self> {
+        let name = def.name.as_ref()?.name.clone();
+        let kind = match def.ty {
+            ContractTy::Abstract(_) => SolContractKind::AbstractContract,
+            ContractTy::Contract(_) => SolContractKind::Contract,
+            ContractTy::Interface(_) => SolContractKind::Interface,
+            ContractTy::Library(_) => SolContractKind::Library,
+        };
+        let bases = def.base.iter().map(|base| base.name.to_string()).collect();
+
+        let mut items = Vec::new();
+        let mut prev_end = def.loc.start();
+        for part in &def.parts {
+            let loc = part.loc();
+            let docs = parse_natspec(comments, prev_end, loc.start());
+            prev_end = loc.end();
+            let item = match part {
+                ContractPart::FunctionDefinition(f) => {
+                    let (kind, name) = match f.ty {
+                        FunctionTy::Constructor => (SolItemKind::Constructor, "constructor"),
+                        FunctionTy::Fallback => (SolItemKind::Fallback, "fallback"),
+                        FunctionTy::Receive => (SolItemKind::Receive, "receive"),
+                        FunctionTy::Modifier => (SolItemKind::Modifier, ""),
+                        FunctionTy::Function => (SolItemKind::Function, ""),
+                    };
+                    let name = f.name.as_ref().map_or(name, |name| name.name.as_str());
+                    let params = f.params.iter().filter_map(|(_, p)| p.as_ref()).map(|p| &p.ty);
+                    SolItem::new(kind, name, params, loc, docs)
+                }
+                ContractPart::EventDefinition(e) => {
+                    let Some(name) = &e.name else { continue };
+                    let params = e.fields.iter().map(|p| &p.ty);
+                    SolItem::new(SolItemKind::Event, &name.name, params, loc, docs)
+                }
+                ContractPart::ErrorDefinition(e) => {
+                    let Some(name) = &e.name else { continue };
+                    let params = e.fields.iter().map(|p| &p.ty);
+                    SolItem::new(SolItemKind::Error, &name.name, params, loc, docs)
+                }
+                _ => continue,
+            };
+            items.push(item);
+        }
+
+        let mut deploys = Vec::<String>::new();
+        let mut on_expression = |expr: &Expression| {
+            if let Expression::New(_, ty) = expr {
+                if let Some(deployed) = deployed_contract(ty) {
+                    if !deploys.iter().any(|d| d == deployed) {
+                        deploys.push(deployed.to_string());
+                    }
+                }
+            }
+        };
+        for base in &def.base {
+            base.args.iter().flatten().for_each(|arg| walk_expression(arg, &mut on_expression));
+        }
+        for part in &def.parts {
+            match part {
+                ContractPart::FunctionDefinition(f) => {
+                    for attr in &f.attributes {
+                        if let FunctionAttribute::BaseOrModifier(_, base) = attr {
+                            for arg in base.args.iter().flatten() {
+                                walk_expression(arg, &mut on_expression);
+                            }
+                        }
+                    }
+                    if let Some(body) = &f.body {
+                        walk_statement(body, &mut on_expression);
+                    }
+                }
+                ContractPart::VariableDefinition(var) => {
+                    if let Some(initializer) = &var.initializer {
+                        walk_expression(initializer, &mut on_expression);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let loc = loc_range(def.loc);
+        Some(Self { name, kind, bases, items, natspec, deploys, loc })
+    }
+
+    /// Returns all functions of the contract
+    pub fn functions(&self) -> impl Iterator<Item = &SolItem> {
+        self.items.iter().filter(|item| item.kind == SolItemKind::Function)
+    }
+
+    /// Returns `true` if this is a test contract, i.e. a contract with a `test` function
+    pub fn is_test(&self) -> bool {
+        matches!(self.kind, SolContractKind::Contract)
+            && self.functions().any(|f| f.name.starts_with("test"))
+    }
+}
+
+/// The kind of a [SolContract]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolContractKind {
+    Contract,
+    AbstractContract,
+    Interface,
+    Library,
+}
+
+/// A function, modifier, event or error of a [SolContract]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolItem {
+    pub kind: SolItemKind,
+    pub name: String,
+    /// The signature of the item, like `transfer(address,uint256)`.
+    ///
+    /// Parameter types are printed as written, so user defined types are not resolved.
+    pub signature: String,
+    /// The NatSpec tags of the item
+    pub natspec: Vec<NatSpecTag>,
+    /// The byte range of the definition in the file
+    pub loc: Range<usize>,
+}
+
+impl SolItem {
+    fn new<'a>(
+        kind: SolItemKind,
+        name: &str,
+        params: impl Iterator<Item = &'a solang_parser::pt::Expression>,
+        loc: Loc,
+        natspec: Vec<NatSpecTag>,
+    ) -> Self {
+        let params = params.map(|ty| ty.to_string()).collect::<Vec<_>>();
+        Self {
+            kind,
+            name: name.to_string(),
+            signature: format!("{name}({})", params.join(",")),
+            natspec,
+            loc: loc_range(loc),
+        }
+    }
+}
+
+/// The kind of a [SolItem]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolItemKind {
+    Function,
+    Constructor,
+    Fallback,
+    Receive,
+    Modifier,
+    Event,
+    Error,
+}
+
+/// A NatSpec tag, like `@notice` or `@param`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NatSpecTag {
+    /// The tag without the leading `@`, untagged comments are `notice`
+    pub tag: String,
+    pub value: String,
+}
+
+/// Returns the NatSpec tags of all doc comments between `start` and `end`
+fn parse_natspec(comments: &[Comment], start: usize, end: usize) -> Vec<NatSpecTag> {
+    parse_doccomments(comments, start, end)
+        .into_iter()
+        .flat_map(DocComment::into_comments)
+        .map(|tag| NatSpecTag { tag: tag.tag, value: tag.value })
+        .collect()
+}
+
+fn loc_range(loc: Loc) -> Range<usize> {
+    match loc {
+        Loc::File(_, start, end) => start..end,
+        _ => 0..0,
+    }
+}
+
 /// Represents an item in a solidity file with its location in the file
 #[derive(Debug, Clone)]
 pub struct SolDataUnit<T> {
@@ -337,4 +724,109 @@ import { FloatMath as Math, Math as FloatMath } from "./Math.sol";
             ]
         );
     }
+
+    #[test]
+    fn libraries_line_up_with_library_contracts() {
+        let content = r#"
+library Inlined { function f() internal {} }
+contract C { function g() external {} }
+library Linked { function f() public {} }
+"#;
+        let data = SolData::parse(content, Path::new("Lib.sol"));
+        let libraries = data
+            .contracts
+            .iter()
+            .filter(|contract| contract.kind == SolContractKind::Library)
+            .zip(&data.libraries)
+            .map(|(contract, library)| (contract.name.as_str(), library.is_inlined()))
+            .collect::<Vec<_>>();
+        assert_eq!(data.libraries.len(), 2);
+        assert_eq!(libraries, [("Inlined", true), ("Linked", false)]);
+    }
+
+    #[test]
+    fn can_index_contracts() {
+        let content = r#"
+pragma solidity ^0.8.0;
+
+/// @title A token
+/// @author someone
+abstract contract Token is Base, IERC20(1) {
+    /// Emitted on transfers
+    event Transfer(address indexed from, address indexed to, uint amount);
+    error Insufficient(uint256 balance);
+
+    constructor(string memory name) {}
+
+    /// @notice Transfers tokens
+    /// @param to the recipient
+    function transfer(address to, uint256[] calldata amounts) external returns (bool) {
+        // not a doc comment
+        return true;
+    }
+
+    modifier onlyOwner() { _; }
+}
+
+interface IToken {}
+
+contract TokenTest {
+    Vault vault = new Vault();
+
+    function testTransfer() public {
+        new Token{salt: 0}("name");
+        bytes memory data = new bytes(1);
+        // new Commented();
+        string memory s = "new Quoted()";
+        if (data.length > 0) {
+            new IToken ();
+        }
+        new Lib.Pool();
+    }
+}
+"#;
+        let data = SolData::parse(content, Path::new("Token.sol"));
+        let [token, itoken, test] = &data.contracts[..] else { panic!("{:?}", data.contracts) };
+
+        assert_eq!(token.name, "Token");
+        assert_eq!(token.kind, SolContractKind::AbstractContract);
+        assert_eq!(token.bases, vec!["Base".to_string(), "IERC20".to_string()]);
+        assert_eq!(
+            token.natspec,
+            vec![
+                NatSpecTag { tag: "title".into(), value: "A token".into() },
+                NatSpecTag { tag: "author".into(), value: "someone".into() },
+            ]
+        );
+        assert!(content[token.loc.clone()].starts_with("abstract contract Token"));
+        assert!(content[token.loc.clone()].ends_with('}'));
+
+        let items =
+            token.items.iter().map(|item| (item.kind, item.signature.as_str())).collect::<Vec<_>>();
+        assert_eq!(
+            items,
+            vec![
+                (SolItemKind::Event, "Transfer(address,address,uint256)"),
+                (SolItemKind::Error, "Insufficient(uint256)"),
+                (SolItemKind::Constructor, "constructor(string)"),
+                (SolItemKind::Function, "transfer(address,uint256[])"),
+                (SolItemKind::Modifier, "onlyOwner()"),
+            ]
+        );
+        assert_eq!(
+            token.items[0].natspec,
+            vec![NatSpecTag { tag: "notice".into(), value: "Emitted on transfers".into() }]
+        );
+        let transfer = &token.items[3];
+        assert_eq!(transfer.natspec.len(), 2);
+        assert_eq!(transfer.natspec[1].value, "to the recipient");
+        assert!(content[transfer.loc.clone()].starts_with("function transfer("));
+        assert!(token.items[4].natspec.is_empty());
+
+        assert_eq!(itoken.kind, SolContractKind::Interface);
+        assert!(!token.is_test());
+        assert!(test.is_test());
+        assert_eq!(test.deploys, vec!["Vault", "Token", "IToken", "Pool"]);
+        assert!(token.deploys.is_empty());
+    }
 }
@@ -0,0 +1,74 @@
+//! Policies for selecting the compiler version of a set of sources.
+
+use crate::compilers::CompilerVersion;
+use semver::Version;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// Determines which compiler version is selected if multiple versions satisfy the version
+/// requirements of a source file and all its imports.
+///
+/// See also [`Graph::into_sources_by_version_with_policy()`](crate::Graph)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum VersionSelectionPolicy {
+    /// Selects the latest matching version, regardless of whether it's installed.
+    Latest,
+    /// Selects the lowest matching version.
+    Minimal,
+    /// Selects the latest installed matching version, or the latest matching version if none is
+    /// installed.
+    #[default]
+    PreferInstalled,
+    /// Pins all input files in a directory to a version. Relative directories are relative to the
+    /// project root, and the most specific directory wins.
+    ///
+    /// Files outside of any of the directories are resolved like [`Self::PreferInstalled`].
+    PinnedPerDirectory(BTreeMap<PathBuf, Version>),
+    /// Same as [`Self::PreferInstalled`], but fails if the sources can't be compiled with a single
+    /// version.
+    SingleVersion,
+}
+
+impl VersionSelectionPolicy {
+    /// Returns the version the given file is pinned to, if any.
+    pub fn pinned_version(&self, file: &Path, root: &Path) -> Option<&Version> {
+        let Self::PinnedPerDirectory(dirs) = self else { return None };
+        dirs.iter()
+            .map(|(dir, version)| (root.join(dir), version))
+            .filter(|(dir, _)| file.starts_with(dir))
+            .max_by_key(|(dir, _)| dir.components().count())
+            .map(|(_, version)| version)
+    }
+
+    /// Returns `true` if only a single version may be selected for all sources.
+    pub fn is_single_version(&self) -> bool {
+        matches!(self, Self::SingleVersion)
+    }
+
+    /// Returns the position of the preferred version in the non-empty `candidates`.
+    pub(crate) fn select(&self, candidates: &[&CompilerVersion]) -> usize {
+        debug_assert!(!candidates.is_empty());
+        let latest = |installed_only: bool| {
+            candidates
+                .iter()
+                .enumerate()
+                .filter(|(_, v)| !installed_only || v.is_installed())
+                .max_by(|(_, a), (_, b)| a.as_ref().cmp(b.as_ref()))
+                .map(|(pos, _)| pos)
+        };
+        match self {
+            Self::Latest => latest(false),
+            Self::Minimal => candidates
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| a.as_ref().cmp(b.as_ref()))
+                .map(|(pos, _)| pos),
+            Self::PreferInstalled | Self::PinnedPerDirectory(_) | Self::SingleVersion => {
+                latest(true).or_else(|| latest(false))
+            }
+        }
+        .expect("not empty; qed.")
+    }
+}
@@ -497,6 +497,75 @@ pub fn parse(input: &str) -> Result<SourceMap, SyntaxError> {
     Parser::new(input).collect()
 }
 
+/// Serializes a source map into the compressed `s:l:f:j:m` format of solc, which is the inverse of
+/// [`parse()`].
+///
+/// Like solc, fields that are equal to the field of the previous element are omitted, as well as
+/// trailing empty fields. The offset and length of instructions that aren't associated with a
+/// source file are written as `-1`.
+pub fn encode(source_map: &[SourceElement]) -> String {
+    let mut out = String::new();
+    let mut prev: Option<&SourceElement> = None;
+
+    for element in source_map {
+        let location = |value: usize| {
+            if value == 0 && element.index.is_none() {
+                "-1".to_string()
+            } else {
+                value.to_string()
+            }
+        };
+        let fields = [
+            field(prev.map(|p| p.offset), element.offset, location),
+            field(prev.map(|p| p.length), element.length, location),
+            field(prev.map(|p| p.index), element.index, |index| {
+                index.map(|i| i as i64).unwrap_or(-1).to_string()
+            }),
+            field(prev.map(|p| p.jump), element.jump, |jump| jump.to_string()),
+            field(prev.map(|p| p.modifier_depth), element.modifier_depth, |depth| {
+                depth.to_string()
+            }),
+        ];
+
+        if prev.is_some() {
+            out.push(';');
+        }
+        let len = fields.iter().rposition(Option::is_some).map_or(0, |idx| idx + 1);
+        for (idx, field) in fields[..len].iter().enumerate() {
+            if idx > 0 {
+                out.push(':');
+            }
+            out.push_str(field.as_deref().unwrap_or_default());
+        }
+        prev = Some(element);
+    }
+
+    out
+}
+
+/// Returns the formatted value if it differs from the value of the previous element.
+fn field<T: PartialEq>(prev: Option<T>, value: T, f: impl FnOnce(T) -> String) -> Option<String> {
+    (prev.as_ref() != Some(&value)).then(|| f(value))
+}
+
+/// Rewrites the source indices of the source map.
+///
+/// This is needed when the sources of the source map are assigned different ids, for example when
+/// merging the output of multiple compiler runs, or after filtering sources. Instructions whose
+/// source is mapped to `None` are no longer associated with any source file, like instructions of
+/// compiler-generated code.
+pub fn remap_indices(source_map: &mut [SourceElement], mut f: impl FnMut(u32) -> Option<u32>) {
+    for element in source_map {
+        if let Some(index) = element.index {
+            element.index = f(index);
+            if element.index.is_none() {
+                element.offset = 0;
+                element.length = 0;
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -520,4 +589,27 @@ mod tests {
         let _map = parser.collect::<Result<SourceMap, _>>().unwrap();
         assert_eq!(out, s);
     }
+
+    #[test]
+    fn can_encode_source_maps() {
+        let s = include_str!("../test-data/cheatcodes.sol-sourcemap.txt");
+        assert_eq!(encode(&parse(s).unwrap()), s);
+
+        let source_maps = include_str!("../test-data/out-source-maps.txt");
+        for (line, s) in source_maps.lines().enumerate() {
+            let map = parse(s).unwrap();
+            assert_eq!(parse(&encode(&map)).unwrap(), map, "line {line}");
+        }
+    }
+
+    #[test]
+    fn can_remap_indices() {
+        let mut map = parse("0:10:0:-:0;2:3:1;;5:1:2:i;8:9:-1").unwrap();
+        remap_indices(&mut map, |index| match index {
+            0 => Some(1),
+            1 => Some(0),
+            _ => None,
+        });
+        assert_eq!(encode(&map), "0:10:1:-:0;2:3:0;;-1:-1:-1:i;8:9");
+    }
 }
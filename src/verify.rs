@@ -0,0 +1,279 @@
+//! Offline verification of deployed bytecode against the artifacts of a project.
+
+use crate::{
+    artifacts::{CompactDeployedBytecode, Offsets},
+    cbor,
+    debug::{instruction_pcs, DebugInfo, ResolvedLocation},
+    error::{Result, SolcError},
+};
+use alloy_primitives::{Address, Bytes};
+use std::{collections::BTreeMap, ops::Range};
+
+/// The result of [`BytecodeVerifier::verify()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BytecodeMatch<'a> {
+    /// The code matches the artifact, including the metadata
+    Exact,
+    /// The code matches the artifact, except for the metadata, which means that it was compiled
+    /// from different sources or settings that don't affect the code, like comments
+    ///
+    /// This includes the metadata of contracts whose creation code is embedded, like the contracts
+    /// deployed by a factory, as long as the embedded metadata has the same length.
+    Partial,
+    /// The code doesn't match the artifact
+    Mismatch(BytecodeMismatch<'a>),
+}
+
+impl BytecodeMatch<'_> {
+    /// Returns `true` if the code matches the artifact, ignoring the metadata
+    pub fn is_match(&self) -> bool {
+        !matches!(self, BytecodeMatch::Mismatch(_))
+    }
+}
+
+/// The first difference between the deployed code and the artifact.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BytecodeMismatch<'a> {
+    /// The offset of the first differing byte, which is the length of the shorter code if one is
+    /// a prefix of the other
+    pub offset: usize,
+    /// The program counter of the instruction of the artifact that contains the offset
+    pub pc: Option<usize>,
+    /// The source location of that instruction, if the verifier has [DebugInfo]
+    pub location: Option<ResolvedLocation<'a>>,
+}
+
+/// Compares deployed runtime bytecode, for example fetched from a node, with the deployed bytecode
+/// of an artifact.
+///
+/// The comparison ignores:
+///  - the CBOR metadata appended by the compiler, see [`BytecodeMatch::Partial`]
+///  - the values of immutable variables, which are only known after deployment
+///  - the addresses of linked libraries, unless they're set with [`Self::with_library()`]
+///
+/// # Example
+///
+/// ```no_run
+/// use foundry_compilers::{verify::BytecodeVerifier, ConfigurableContractArtifact};
+///
+/// let artifact: ConfigurableContractArtifact =
+///     serde_json::from_str(&std::fs::read_to_string("out/Counter.sol/Counter.json")?)?;
+/// let code = alloy_primitives::hex::decode(std::fs::read_to_string("Counter.hex")?.trim())?;
+/// let verifier = BytecodeVerifier::new(artifact.deployed_bytecode.unwrap());
+/// println!("{:?}", verifier.verify(&code)?);
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct BytecodeVerifier {
+    bytecode: CompactDeployedBytecode,
+    libraries: BTreeMap<String, BTreeMap<String, Address>>,
+    debug_info: Option<DebugInfo>,
+}
+
+impl BytecodeVerifier {
+    /// Creates a verifier for the deployed bytecode of an artifact.
+    ///
+    /// [`DeployedBytecode`](crate::artifacts::DeployedBytecode) can be converted as well.
+    pub fn new(bytecode: impl Into<CompactDeployedBytecode>) -> Self {
+        Self { bytecode: bytecode.into(), libraries: Default::default(), debug_info: None }
+    }
+
+    /// Sets the expected address of a library, by the file and name of its link reference.
+    ///
+    /// Library slots without an address match any address.
+    #[must_use]
+    pub fn with_library(
+        mut self,
+        file: impl Into<String>,
+        name: impl Into<String>,
+        address: Address,
+    ) -> Self {
+        self.libraries.entry(file.into()).or_default().insert(name.into(), address);
+        self
+    }
+
+    /// Sets the debug info of the deployed bytecode, which is used to resolve the source location
+    /// of a mismatch, see [`DebugInfo::deployed()`].
+    #[must_use]
+    pub fn with_debug_info(mut self, debug_info: DebugInfo) -> Self {
+        self.debug_info = Some(debug_info);
+        self
+    }
+
+    /// Compares the deployed code with the artifact.
+    ///
+    /// Returns an error if the artifact doesn't have valid deployed bytecode.
+    pub fn verify(&self, code: &[u8]) -> Result<BytecodeMatch<'_>> {
+        let bytecode = self
+            .bytecode
+            .bytecode
+            .as_ref()
+            .ok_or_else(|| SolcError::msg("missing deployed bytecode"))?;
+        let expected =
+            self.expected_code().ok_or_else(|| SolcError::msg("invalid bytecode object"))?;
+
+        // bytes that are set at link or deploy time and that match anything
+        let mut ignored = Vec::new();
+        for (file, libraries) in &bytecode.link_references {
+            for (name, offsets) in libraries {
+                if self.library(file, name).is_none() {
+                    ignored.extend(offsets.iter().map(range));
+                }
+            }
+        }
+        ignored.extend(self.bytecode.immutable_references.values().flatten().map(range));
+
+        let expected_code = cbor::strip_metadata(&expected);
+        let actual_code = cbor::strip_metadata(code);
+        // the metadata of contracts whose creation code is embedded, like the children of a factory
+        let embedded_metadata = cbor::metadata_ranges(expected_code);
+
+        let mut metadata_matches = expected[expected_code.len()..] == code[actual_code.len()..];
+        let mut mismatch = None;
+        for (offset, (a, b)) in expected_code.iter().zip(actual_code).enumerate() {
+            if a == b || ignored.iter().any(|r| r.contains(&offset)) {
+                continue;
+            }
+            if embedded_metadata.iter().any(|r| r.contains(&offset)) {
+                metadata_matches = false;
+                continue;
+            }
+            mismatch = Some(offset);
+            break;
+        }
+        let mismatch = mismatch.or_else(|| {
+            (expected_code.len() != actual_code.len())
+                .then(|| expected_code.len().min(actual_code.len()))
+        });
+
+        let Some(offset) = mismatch else {
+            return Ok(if metadata_matches {
+                BytecodeMatch::Exact
+            } else {
+                BytecodeMatch::Partial
+            });
+        };
+
+        let pcs = instruction_pcs(&expected);
+        let pc = pcs.partition_point(|pc| *pc <= offset).checked_sub(1).map(|idx| pcs[idx]);
+        let location =
+            pc.zip(self.debug_info.as_ref()).and_then(|(pc, debug_info)| debug_info.resolve(pc));
+        Ok(BytecodeMatch::Mismatch(BytecodeMismatch { offset, pc, location }))
+    }
+
+    /// Returns the deployed bytecode of the artifact, with the expected library addresses linked
+    /// and the other placeholders zeroed.
+    pub fn expected_code(&self) -> Option<Bytes> {
+        let bytecode = self.bytecode.bytecode.as_ref()?;
+        let mut code = bytecode.object.bytes_with_zeroed_placeholders()?.to_vec();
+        for (file, libraries) in &bytecode.link_references {
+            for (name, offsets) in libraries {
+                let Some(address) = self.library(file, name) else { continue };
+                for offsets in offsets {
+                    if let Some(slot) = code.get_mut(range(offsets)) {
+                        slot.copy_from_slice(address.as_slice());
+                    }
+                }
+            }
+        }
+        Some(code.into())
+    }
+
+    fn library(&self, file: &str, name: &str) -> Option<&Address> {
+        self.libraries.get(file)?.get(name)
+    }
+}
+
+fn range(offsets: &Offsets) -> Range<usize> {
+    offsets.start as usize..(offsets.start + offsets.length) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debug::DebugSource;
+    use alloy_primitives::hex;
+    use std::path::Path;
+
+    #[test]
+    fn can_verify_deployed_bytecode() {
+        // PUSH1 0x80, PUSH20 <Lib>, PUSH32 <immutable>, PUSH1 0x01, STOP, `{"solc": 0.8.19}`
+        let placeholder = format!("__${}$__", "a".repeat(34));
+        let bytecode: CompactDeployedBytecode = serde_json::from_value(serde_json::json!({
+            "object": format!("608073{placeholder}7f{}600100a164736f6c6343000813000a", "00".repeat(32)),
+            "sourceMap": "0:28:0:-:0;;;20:5;",
+            "linkReferences": { "src/Lib.sol": { "Lib": [{ "start": 3, "length": 20 }] } },
+            "immutableReferences": { "5": [{ "start": 24, "length": 32 }] }
+        }))
+        .unwrap();
+        let content = "contract A {\n  uint x = 1;\n}";
+        let debug_info = DebugInfo::new(
+            &bytecode.bytecode.as_ref().unwrap().object.bytes_with_zeroed_placeholders().unwrap(),
+            bytecode.source_map().unwrap().unwrap(),
+            [(0, DebugSource::new("src/A.sol", content))],
+        );
+        let verifier = BytecodeVerifier::new(bytecode).with_debug_info(debug_info);
+
+        let deployed = |value: &str, metadata: &str| {
+            hex::decode(format!(
+                "608073{}7f{}60{value}00a164736f6c63430008{metadata}000a",
+                "11".repeat(20),
+                "22".repeat(32)
+            ))
+            .unwrap()
+        };
+
+        assert_eq!(verifier.verify(&deployed("01", "13")).unwrap(), BytecodeMatch::Exact);
+        assert_eq!(verifier.verify(&deployed("01", "14")).unwrap(), BytecodeMatch::Partial);
+
+        let code = deployed("02", "13");
+        let BytecodeMatch::Mismatch(mismatch) = verifier.verify(&code).unwrap() else {
+            panic!("expected mismatch")
+        };
+        assert_eq!(mismatch.offset, 57);
+        assert_eq!(mismatch.pc, Some(56));
+        let location = mismatch.location.unwrap();
+        assert_eq!(location.path(), Path::new("src/A.sol"));
+        assert_eq!(location.snippet(), "x = 1");
+        assert_eq!((location.line, location.column), (2, 8));
+
+        // the code is shorter than the artifact
+        let BytecodeMatch::Mismatch(mismatch) = verifier.verify(&code[..40]).unwrap() else {
+            panic!("expected mismatch")
+        };
+        assert_eq!(mismatch.offset, 40);
+
+        let verifier = verifier.with_library("src/Lib.sol", "Lib", Address::repeat_byte(0x33));
+        let BytecodeMatch::Mismatch(mismatch) = verifier.verify(&deployed("01", "13")).unwrap()
+        else {
+            panic!("expected mismatch")
+        };
+        assert_eq!(mismatch.offset, 3);
+        assert_eq!(mismatch.location.unwrap().snippet(), content);
+    }
+
+    #[test]
+    fn can_verify_embedded_metadata() {
+        // the creation code of a child contract with its metadata, then the parent's metadata
+        let child = |solc: &str| format!("6080a164736f6c63430008{solc}000a");
+        let bytecode: CompactDeployedBytecode = serde_json::from_value(serde_json::json!({
+            "object": format!("6000{}00a164736f6c6343000813000a", child("13")),
+        }))
+        .unwrap();
+        let verifier = BytecodeVerifier::new(bytecode);
+        let deployed = |child: String, solc: &str| {
+            hex::decode(format!("6000{child}00a164736f6c63430008{solc}000a")).unwrap()
+        };
+
+        assert_eq!(verifier.verify(&deployed(child("13"), "13")).unwrap(), BytecodeMatch::Exact);
+        assert_eq!(verifier.verify(&deployed(child("14"), "13")).unwrap(), BytecodeMatch::Partial);
+        assert_eq!(verifier.verify(&deployed(child("14"), "14")).unwrap(), BytecodeMatch::Partial);
+
+        let BytecodeMatch::Mismatch(mismatch) =
+            verifier.verify(&deployed(child("13").replacen("6080", "6081", 1), "13")).unwrap()
+        else {
+            panic!("expected mismatch")
+        };
+        assert_eq!(mismatch.offset, 3);
+    }
+}
@@ -570,6 +570,52 @@ fn can_flatten_file_in_dapp_sample() {
     });
 }
 
+#[test]
+fn can_flatten_cached_sources() {
+    let project = TempProject::dapptools().unwrap();
+
+    project
+        .add_source(
+            "Lib",
+            r#"
+pragma solidity ^0.8.10;
+contract Lib { }
+"#,
+        )
+        .unwrap();
+    let target = project
+        .add_source(
+            "Target",
+            r#"
+pragma solidity ^0.8.10;
+import "./Lib.sol";
+contract Target is Lib { }
+"#,
+        )
+        .unwrap();
+
+    project.compile().unwrap().assert_success();
+    let output = project.compile().unwrap();
+    output.assert_success();
+    assert!(output.is_unchanged());
+
+    let result = Flattener::new(project.project(), &output, &target).unwrap().flatten();
+    assert_eq!(result, project.flatten(&target).unwrap());
+    assert_eq!(
+        result,
+        r#"pragma solidity ^0.8.10;
+
+// src/Lib.sol
+
+contract Lib { }
+
+// src/Target.sol
+
+contract Target is Lib { }
+"#
+    );
+}
+
 #[test]
 fn can_flatten_unique() {
     let project = TempProject::dapptools().unwrap();